@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
 use crate::GameState;
 
@@ -14,6 +15,42 @@ pub struct RectCollisionShape {
     pub collide: bool,
 }
 
+#[derive(Component, Reflect, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Surface {
+    #[default]
+    Normal,
+    Ice,
+    Sticky,
+}
+
+impl Surface {
+    /// Multiplier applied to the acceleration and deceleration of anything standing on this surface.
+    pub fn friction(&self) -> f32 {
+        match self {
+            Surface::Normal => 1.,
+            Surface::Ice => 0.15,
+            Surface::Sticky => 2.5,
+        }
+    }
+
+    /// Multiplier applied to the top speed of anything standing on this surface.
+    pub fn speed_factor(&self) -> f32 {
+        match self {
+            Surface::Sticky => 0.6,
+            _ => 1.,
+        }
+    }
+}
+
+/// The surface an entity is currently standing on, or `None` if it is airborne.
+#[derive(Component, Reflect, Default)]
+pub struct Grounded(pub Option<Surface>);
+
+/// Horizontal direction of the wall an entity is pressed against, or `None` if it is not touching one.
+#[derive(Component, Reflect, Default)]
+pub struct WallContact(pub Option<f32>);
+
 #[derive(Component)]
 pub struct ScreenFade {
     pub fade_color: Color,
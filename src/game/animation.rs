@@ -0,0 +1,357 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use serde::Deserialize;
+
+use super::JsonLoaderError;
+
+/// Named animations for one kind of entity, loaded from an `.anim.json` file.
+///
+/// Each animation is a single row of frames cut from a sprite sheet, for example:
+///
+/// ```json
+/// {
+///   "run": {
+///     "sheet": "player/player_run.png",
+///     "frame_size": [32.0, 32.0],
+///     "frames": 5,
+///     "fps": 16.0,
+///     "mode": "loop",
+///     "events": [{ "frame": 2, "name": "footstep" }]
+///   }
+/// }
+/// ```
+#[derive(TypeUuid, Asset, TypePath)]
+#[uuid = "0c3d8e57-9a41-4f0b-8a6e-5b2f7d1e4c93"]
+pub struct AnimationLibrary(HashMap<String, AnimationDefinition>);
+
+pub struct AnimationDefinition {
+    atlas: Handle<TextureAtlas>,
+    frames: usize,
+    fps: f32,
+    mode: LoopMode,
+    events: Vec<FrameEvent>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopMode {
+    #[default]
+    Loop,
+    /// Stops on the last frame.
+    Once,
+    /// Plays forwards, then backwards, and so on.
+    PingPong,
+}
+
+/// Names a frame so that systems can react when an animation reaches it, such as footsteps.
+#[derive(Deserialize)]
+struct FrameEvent {
+    frame: usize,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct AnimationData {
+    sheet: String,
+    frame_size: Vec2,
+    frames: usize,
+    fps: f32,
+    #[serde(default)]
+    mode: LoopMode,
+    #[serde(default)]
+    events: Vec<FrameEvent>,
+}
+
+impl AnimationData {
+    /// Animation files are written by hand, so catch values that would break playback.
+    fn validate(&self) -> Result<(), &'static str> {
+        if self.frames == 0 {
+            Err("must have at least one frame")
+        } else if !self.fps.is_finite() || self.fps <= 0. {
+            Err("fps must be a positive number")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Sent when an animation reaches a frame that has an event attached to it.
+#[derive(Event)]
+pub struct AnimationFrameEvent {
+    pub entity: Entity,
+    pub name: String,
+}
+
+/// Plays an animation from a library on the entity's `TextureAtlasSprite`, switching the atlas
+/// as needed.
+#[derive(Component)]
+pub struct SpriteAnimation {
+    library: Handle<AnimationLibrary>,
+    animation: String,
+    pub frame: usize,
+    /// Multiplies the frame rate. At 0 the current frame is held.
+    pub speed: f32,
+    timer: Timer,
+    reverse: bool,
+    finished: bool,
+    /// Whether the first frame has been shown, so that its events are sent once.
+    started: bool,
+}
+
+impl SpriteAnimation {
+    pub fn new(library: Handle<AnimationLibrary>, animation: &str) -> Self {
+        Self {
+            library,
+            animation: animation.to_string(),
+            frame: 0,
+            speed: 1.,
+            timer: Timer::from_seconds(1., TimerMode::Repeating),
+            reverse: false,
+            finished: false,
+            started: false,
+        }
+    }
+
+    /// Switches to another animation from the start, unless it is already playing.
+    pub fn play(&mut self, animation: &str) {
+        if self.animation != animation {
+            self.animation = animation.to_string();
+            self.frame = 0;
+            self.timer.reset();
+            self.reverse = false;
+            self.finished = false;
+            self.started = false;
+        }
+    }
+
+    /// Advances by a number of timer ticks, returning every frame entered on the way, including
+    /// the first frame when the animation has just started.
+    fn step(&mut self, ticks: u32, frames: usize, mode: LoopMode) -> Vec<usize> {
+        let mut entered = Vec::new();
+
+        if !self.started {
+            self.started = true;
+            entered.push(self.frame);
+        }
+
+        for _ in 0..ticks {
+            if self.finished {
+                break;
+            }
+
+            let previous = self.frame;
+            self.advance(frames, mode);
+
+            if self.frame != previous {
+                entered.push(self.frame);
+            }
+        }
+
+        entered
+    }
+
+    fn advance(&mut self, frames: usize, mode: LoopMode) {
+        let last = frames.saturating_sub(1);
+        if last == 0 {
+            return;
+        }
+
+        match mode {
+            LoopMode::Loop => self.frame = (self.frame + 1) % frames,
+            LoopMode::Once if self.frame >= last => self.finished = true,
+            LoopMode::Once => self.frame += 1,
+            LoopMode::PingPong => {
+                if (!self.reverse && self.frame >= last) || (self.reverse && self.frame == 0) {
+                    self.reverse = !self.reverse;
+                }
+
+                self.frame = if self.reverse {
+                    self.frame - 1
+                } else {
+                    self.frame + 1
+                };
+            }
+        }
+    }
+}
+
+pub struct SpriteAnimationPlugin;
+
+impl Plugin for SpriteAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<AnimationLibrary>()
+            .init_asset_loader::<AnimationLibraryLoader>()
+            .add_event::<AnimationFrameEvent>()
+            .add_systems(Update, sprite_animation_system);
+    }
+}
+
+#[derive(Default)]
+struct AnimationLibraryLoader;
+
+impl AssetLoader for AnimationLibraryLoader {
+    type Asset = AnimationLibrary;
+    type Settings = ();
+    type Error = JsonLoaderError;
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.json"]
+    }
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let data: HashMap<String, AnimationData> = serde_json::from_slice(&bytes)?;
+
+            for (name, animation) in &data {
+                animation
+                    .validate()
+                    .map_err(|e| JsonLoaderError::InvalidAnimation(name.clone(), e))?;
+            }
+
+            let animations = data
+                .into_iter()
+                .map(|(name, animation)| {
+                    let texture = load_context.load(animation.sheet);
+                    let atlas = TextureAtlas::from_grid(
+                        texture,
+                        animation.frame_size,
+                        animation.frames,
+                        1,
+                        None,
+                        None,
+                    );
+                    let definition = AnimationDefinition {
+                        atlas: load_context.add_labeled_asset(name.clone(), atlas),
+                        frames: animation.frames,
+                        fps: animation.fps,
+                        mode: animation.mode,
+                        events: animation.events,
+                    };
+                    (name, definition)
+                })
+                .collect();
+
+            Ok(AnimationLibrary(animations))
+        })
+    }
+}
+
+pub fn sprite_animation_system(
+    time: Res<Time>,
+    libraries: Res<Assets<AnimationLibrary>>,
+    mut events: EventWriter<AnimationFrameEvent>,
+    mut query: Query<(
+        Entity,
+        &mut SpriteAnimation,
+        &mut Handle<TextureAtlas>,
+        &mut TextureAtlasSprite,
+    )>,
+) {
+    for (entity, mut animation, mut atlas, mut sprite) in query.iter_mut() {
+        let Some(definition) = libraries
+            .get(&animation.library)
+            .and_then(|library| library.0.get(&animation.animation))
+        else {
+            continue;
+        };
+
+        if *atlas != definition.atlas {
+            *atlas = definition.atlas.clone();
+        }
+
+        let speed = animation.speed.max(0.);
+        animation
+            .timer
+            .set_duration(Duration::from_secs_f32(1. / definition.fps));
+        animation.timer.tick(time.delta().mul_f32(speed));
+
+        let ticks = animation.timer.times_finished_this_tick();
+
+        for frame in animation.step(ticks, definition.frames, definition.mode) {
+            events.send_batch(
+                definition
+                    .events
+                    .iter()
+                    .filter(|event| event.frame == frame)
+                    .map(|event| AnimationFrameEvent {
+                        entity,
+                        name: event.name.clone(),
+                    }),
+            );
+        }
+
+        sprite.index = animation.frame.min(definition.frames.saturating_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(mode: LoopMode, steps: usize) -> Vec<usize> {
+        let mut animation = SpriteAnimation::new(Handle::default(), "test");
+        (0..steps)
+            .map(|_| {
+                animation.advance(3, mode);
+                animation.frame
+            })
+            .collect()
+    }
+
+    #[test]
+    fn advances_frames_by_loop_mode() {
+        assert_eq!(frames(LoopMode::Loop, 5), [1, 2, 0, 1, 2]);
+        assert_eq!(frames(LoopMode::Once, 5), [1, 2, 2, 2, 2]);
+        assert_eq!(frames(LoopMode::PingPong, 6), [1, 2, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn enters_each_frame_once() {
+        let entered = |frames: usize, mode: LoopMode, steps: usize| {
+            let mut animation = SpriteAnimation::new(Handle::default(), "test");
+            (0..steps)
+                .flat_map(|_| animation.step(1, frames, mode))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(entered(3, LoopMode::Loop, 4), [0, 1, 2, 0, 1]);
+        assert_eq!(entered(3, LoopMode::Once, 5), [0, 1, 2]);
+        assert_eq!(entered(1, LoopMode::Loop, 5), [0]);
+
+        let mut animation = SpriteAnimation::new(Handle::default(), "test");
+        animation.step(2, 3, LoopMode::Loop);
+        animation.play("other");
+        assert_eq!(animation.step(1, 3, LoopMode::Loop), [0, 1]);
+    }
+
+    #[test]
+    fn rejects_animations_that_cannot_play() {
+        let parse = |json: &str| {
+            serde_json::from_str::<AnimationData>(json)
+                .unwrap()
+                .validate()
+        };
+
+        let valid = r#"{"sheet": "a.png", "frame_size": [8, 8], "frames": 2, "fps": 4}"#;
+        let no_frames = r#"{"sheet": "a.png", "frame_size": [8, 8], "frames": 0, "fps": 4}"#;
+        let no_fps = r#"{"sheet": "a.png", "frame_size": [8, 8], "frames": 2, "fps": 0}"#;
+        let negative_fps = r#"{"sheet": "a.png", "frame_size": [8, 8], "frames": 2, "fps": -1}"#;
+
+        assert!(parse(valid).is_ok());
+        assert!(parse(no_frames).is_err());
+        assert!(parse(no_fps).is_err());
+        assert!(parse(negative_fps).is_err());
+    }
+}
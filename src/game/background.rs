@@ -0,0 +1,190 @@
+use bevy::{
+    prelude::*,
+    render::texture::{
+        ImageAddressMode, ImageLoaderSettings, ImageSampler, ImageSamplerDescriptor,
+    },
+};
+use serde::Deserialize;
+
+use super::{camera::camera_follow_system, intro::intro_system, LevelData, Levels};
+use crate::{GameData, GameState, MainCamera};
+
+/// Extra distance tiled layers are drawn past the edge of the view, so that screen shake does
+/// not reveal their edges.
+const TILING_MARGIN: f32 = 64.;
+/// Depth of the farthest layer. Each layer after it is drawn slightly closer.
+const BACKGROUND_DEPTH: f32 = -100.;
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Tiling {
+    #[default]
+    None,
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+impl Tiling {
+    fn repeats(&self) -> BVec2 {
+        match self {
+            Tiling::None => BVec2::FALSE,
+            Tiling::Horizontal => BVec2::new(true, false),
+            Tiling::Vertical => BVec2::new(false, true),
+            Tiling::Both => BVec2::TRUE,
+        }
+    }
+}
+
+/// A background image that scrolls more slowly than the level, listed from farthest to nearest.
+#[derive(Deserialize)]
+pub struct BackgroundLayerData {
+    image: String,
+    /// How far the layer moves relative to the level, from 0 (stays with the camera) to 1 (moves
+    /// with the level).
+    parallax: f32,
+    #[serde(default)]
+    tiling: Tiling,
+    #[serde(default = "default_tint")]
+    tint: [f32; 4],
+    /// Position of the image center when the camera is at the origin.
+    #[serde(default)]
+    offset: Vec2,
+    #[serde(default = "default_scale")]
+    scale: f32,
+}
+
+fn default_tint() -> [f32; 4] {
+    [1., 1., 1., 1.]
+}
+
+fn default_scale() -> f32 {
+    1.
+}
+
+#[derive(Component)]
+struct BackgroundLayer {
+    parallax: f32,
+    tiling: Tiling,
+    offset: Vec2,
+    scale: f32,
+}
+
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Level), spawn_background)
+            .add_systems(OnExit(GameState::Level), despawn_background)
+            .add_systems(
+                Update,
+                background_system
+                    .after(camera_follow_system)
+                    .after(intro_system)
+                    .run_if(in_state(GameState::Level)),
+            );
+    }
+}
+
+fn spawn_background(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_data: Res<GameData>,
+    level_assets: Res<Assets<LevelData>>,
+    levels: Res<Levels>,
+) {
+    let level_handle = levels.0.get(&game_data.slot().current_level).unwrap();
+    let level_data = level_assets.get(level_handle).unwrap();
+
+    for (i, layer) in level_data.backgrounds.iter().enumerate() {
+        // Tiled layers draw past the edges of the image, which the sampler wraps around
+        let texture = asset_server.load_with_settings(
+            layer.image.clone(),
+            |settings: &mut ImageLoaderSettings| {
+                settings.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+                    address_mode_u: ImageAddressMode::Repeat,
+                    address_mode_v: ImageAddressMode::Repeat,
+                    ..ImageSamplerDescriptor::nearest()
+                });
+            },
+        );
+        let [r, g, b, a] = layer.tint;
+
+        commands.spawn((
+            SpriteBundle {
+                texture,
+                sprite: Sprite {
+                    color: Color::rgba(r, g, b, a),
+                    ..Default::default()
+                },
+                transform: Transform::from_xyz(0., 0., BACKGROUND_DEPTH + i as f32),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            BackgroundLayer {
+                parallax: layer.parallax,
+                tiling: layer.tiling,
+                offset: layer.offset,
+                scale: layer.scale,
+            },
+        ));
+    }
+}
+
+fn despawn_background(mut commands: Commands, query: Query<Entity, With<BackgroundLayer>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn background_system(
+    images: Res<Assets<Image>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    mut layer_query: Query<
+        (
+            &BackgroundLayer,
+            &Handle<Image>,
+            &mut Sprite,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        Without<MainCamera>,
+    >,
+) {
+    let Ok((camera_tf, projection)) = camera_query.get_single() else {
+        return;
+    };
+
+    let camera_pos = camera_tf.translation.truncate();
+    let half_view = projection.area.half_size() + TILING_MARGIN;
+
+    for (layer, texture, mut sprite, mut tf, mut visibility) in layer_query.iter_mut() {
+        let Some(image) = images.get(texture) else {
+            continue;
+        };
+
+        let image_size = image.size_f32();
+        let layer_pos = layer.offset + camera_pos * (1. - layer.parallax);
+        let repeats = layer.tiling.repeats();
+
+        // Tiled axes cover the view and scroll the texture underneath it, other axes show the
+        // whole image once
+        let center = Vec2::select(repeats, camera_pos, layer_pos);
+        let size = Vec2::select(repeats, half_view * 2., image_size * layer.scale);
+
+        // Texture coordinates run downwards from the top left corner of the image
+        let top_left = Vec2::new(center.x - size.x / 2., center.y + size.y / 2.);
+        let texture_min = Vec2::new(top_left.x - layer_pos.x, layer_pos.y - top_left.y)
+            / layer.scale
+            + image_size / 2.;
+
+        sprite.custom_size = Some(size);
+        sprite.rect = Some(Rect::from_corners(
+            texture_min,
+            texture_min + size / layer.scale,
+        ));
+        tf.translation = center.extend(tf.translation.z);
+        *visibility = Visibility::Inherited;
+    }
+}
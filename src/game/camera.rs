@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+
+use super::{player::Player, tuning::GameTuning, LevelBounds};
+use crate::{
+    components::{Grounded, Velocity},
+    GameState, MainCamera, Settings,
+};
+
+#[derive(Event)]
+pub enum CameraEffectEvent {
+    /// Adds trauma, which shakes the camera by its square and decays over time.
+    Shake(f32),
+    /// Briefly zooms in by a fraction of the normal view.
+    ZoomPulse(f32),
+    /// Looks at a point for a number of seconds before returning to the player.
+    Focus(Vec2, f32),
+}
+
+/// Follows a point that only moves once the player leaves the dead zone around it, rather than
+/// following the player directly.
+#[derive(Resource, Default)]
+pub struct CameraRig {
+    /// Smoothed camera position, before any shake is applied.
+    position: Option<Vec2>,
+    focus: Option<Vec2>,
+    look_ahead: f32,
+    trauma: f32,
+    zoom: f32,
+    focus_override: Option<(Vec2, Timer)>,
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraRig>()
+            .add_event::<CameraEffectEvent>()
+            .add_systems(OnEnter(GameState::Level), reset_camera_rig)
+            .add_systems(OnExit(GameState::Level), reset_camera_effects)
+            .add_systems(
+                Update,
+                (camera_effect_system, camera_follow_system)
+                    .chain()
+                    .run_if(in_state(GameState::Level)),
+            );
+    }
+}
+
+/// Moves `current` towards `target` at `rate` per second, independent of frame rate.
+fn smooth(current: f32, target: f32, rate: f32, delta: f32) -> f32 {
+    current + (target - current) * (1. - (-rate * delta).exp())
+}
+
+/// Keeps a view of `half_view` around `target` inside the level, centering on any axis the level
+/// is smaller than.
+pub fn clamp_to_bounds(mut target: Vec2, half_view: Vec2, bounds: Rect) -> Vec2 {
+    let min = bounds.min + half_view;
+    let max = bounds.max - half_view;
+    target.x = if min.x < max.x {
+        target.x.clamp(min.x, max.x)
+    } else {
+        bounds.center().x
+    };
+    target.y = if min.y < max.y {
+        target.y.clamp(min.y, max.y)
+    } else {
+        bounds.center().y
+    };
+    target
+}
+
+fn reset_camera_rig(mut rig: ResMut<CameraRig>) {
+    *rig = CameraRig::default();
+}
+
+fn reset_camera_effects(
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    if let Ok((mut camera_tf, mut projection)) = camera_query.get_single_mut() {
+        camera_tf.rotation = Quat::IDENTITY;
+        projection.scale = 1.;
+    }
+}
+
+fn camera_effect_system(
+    time: Res<Time>,
+    tuning: Res<GameTuning>,
+    settings: Res<Settings>,
+    mut rig: ResMut<CameraRig>,
+    mut events: EventReader<CameraEffectEvent>,
+) {
+    for event in events.read() {
+        match *event {
+            CameraEffectEvent::Shake(trauma) if settings.screen_shake => {
+                rig.trauma = (rig.trauma + trauma).min(1.);
+            }
+            CameraEffectEvent::Shake(_) => {}
+            CameraEffectEvent::ZoomPulse(amount) => rig.zoom = rig.zoom.max(amount),
+            CameraEffectEvent::Focus(position, duration) => {
+                rig.focus_override =
+                    Some((position, Timer::from_seconds(duration, TimerMode::Once)));
+            }
+        }
+    }
+
+    let delta = time.delta_seconds();
+    rig.trauma = (rig.trauma - tuning.camera.trauma_decay * delta).max(0.);
+    rig.zoom = smooth(rig.zoom, 0., tuning.camera.zoom_pulse_decay, delta);
+
+    if let Some((_, timer)) = rig.focus_override.as_mut() {
+        if timer.tick(time.delta()).finished() {
+            rig.focus_override = None;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn camera_follow_system(
+    time: Res<Time>,
+    tuning: Res<GameTuning>,
+    settings: Res<Settings>,
+    bounds: Res<LevelBounds>,
+    mut rig: ResMut<CameraRig>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+    player_query: Query<(&Transform, &Velocity, &Grounded), (With<Player>, Without<MainCamera>)>,
+) {
+    let (Ok((mut camera_tf, mut projection)), Ok((player_tf, velocity, grounded))) =
+        (camera_query.get_single_mut(), player_query.get_single())
+    else {
+        return;
+    };
+
+    let camera = &tuning.camera;
+    let delta = time.delta_seconds();
+    let player_pos = player_tf.translation.truncate();
+
+    let focus = rig.focus.get_or_insert(player_pos);
+    *focus = focus.clamp(player_pos - camera.dead_zone, player_pos + camera.dead_zone);
+    let focus = *focus;
+
+    let look_ahead_target = if velocity.0.x.abs() > 1. {
+        velocity.0.x.signum() * camera.look_ahead
+    } else {
+        rig.look_ahead
+    };
+    rig.look_ahead = smooth(
+        rig.look_ahead,
+        look_ahead_target,
+        camera.look_ahead_smoothing,
+        delta,
+    );
+
+    let target = match &rig.focus_override {
+        Some((position, _)) => *position,
+        None => focus + Vec2::X * rig.look_ahead,
+    };
+
+    projection.scale = 1. / (1. + rig.zoom);
+
+    let target = clamp_to_bounds(target, projection.area.half_size(), bounds.0);
+
+    // Lags behind while jumping so that the screen does not jerk upwards with every jump
+    let vertical_smoothing = if grounded.0.is_none() && velocity.0.y > 0. {
+        camera.jump_vertical_smoothing
+    } else {
+        camera.smoothing
+    };
+
+    let position = match rig.position {
+        Some(position) => Vec2::new(
+            smooth(position.x, target.x, camera.smoothing, delta),
+            smooth(position.y, target.y, vertical_smoothing, delta),
+        ),
+        None => target,
+    };
+    rig.position = Some(position);
+
+    // Shake is applied on top of the smoothed position so that it never feeds back into it
+    let shake = if settings.screen_shake {
+        rig.trauma * rig.trauma
+    } else {
+        0.
+    };
+    let offset = Vec2::new(rand::random::<f32>(), rand::random::<f32>()) * 2. - 1.;
+    let angle = rand::random::<f32>() * 2. - 1.;
+
+    camera_tf.translation =
+        (position + offset * camera.max_shake_offset * shake).extend(camera_tf.translation.z);
+    camera_tf.rotation = Quat::from_rotation_z(angle * camera.max_shake_angle * shake);
+}
@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+
+use crate::{components::RectCollisionShape, GameState, UIAssets};
+
+#[derive(Event)]
+pub struct SpawnClockEvent {
+    pub position: Vec2,
+    pub seconds: f32,
+}
+
+/// Adds its seconds to the stopwatch when picked up. Clocks with negative seconds are hazards.
+#[derive(Component)]
+pub struct Clock {
+    pub seconds: f32,
+}
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnClockEvent>()
+            .add_systems(
+                Update,
+                spawn_clock
+                    .run_if(in_state(GameState::Level).and_then(on_event::<SpawnClockEvent>())),
+            )
+            .add_systems(OnExit(GameState::Level), despawn_clocks);
+    }
+}
+
+fn spawn_clock(
+    mut events: EventReader<SpawnClockEvent>,
+    mut commands: Commands,
+    ui_assets: Res<UIAssets>,
+) {
+    for event in events.read() {
+        let color = if event.seconds < 0. {
+            Color::CRIMSON
+        } else {
+            Color::SEA_GREEN
+        };
+
+        commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::splat(28.)),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(event.position.extend(0.)),
+                    ..Default::default()
+                },
+                Clock {
+                    seconds: event.seconds,
+                },
+                RectCollisionShape {
+                    size: Vec2::splat(28.),
+                    collide: false,
+                },
+            ))
+            .with_children(|clock| {
+                clock.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        format!("{:+}", event.seconds),
+                        TextStyle {
+                            font: ui_assets.font.clone(),
+                            font_size: 20.,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    transform: Transform::from_xyz(0., 0., 0.1),
+                    ..Default::default()
+                });
+            });
+    }
+}
+
+fn despawn_clocks(mut commands: Commands, query: Query<Entity, With<Clock>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
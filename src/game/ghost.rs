@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+
+use super::{
+    animation::SpriteAnimation,
+    player::Player,
+    replay::{player_input_system, GhostFrame, Replay, ReplayPlayback},
+    tuning::GameTuning,
+    LevelData, Levels,
+};
+use crate::{GameAssets, GameData, GameState, Settings};
+
+/// Follows the recorded frames of the fastest completion of the current level.
+#[derive(Component)]
+struct Ghost {
+    frames: Vec<GhostFrame>,
+    tick: usize,
+}
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Level),
+            spawn_ghost.run_if(not(resource_exists::<ReplayPlayback>())),
+        )
+        .add_systems(OnExit(GameState::Level), despawn_ghost)
+        .add_systems(
+            Update,
+            ghost_visibility_system
+                .run_if(in_state(GameState::Level).and_then(resource_changed::<Settings>())),
+        )
+        .add_systems(
+            FixedUpdate,
+            ghost_movement_system
+                .after(player_input_system)
+                .run_if(in_state(GameState::Level)),
+        );
+    }
+}
+
+fn spawn_ghost(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    tuning: Res<GameTuning>,
+    settings: Res<Settings>,
+    game_data: Res<GameData>,
+    level_assets: Res<Assets<LevelData>>,
+    levels: Res<Levels>,
+) {
+    let level = game_data.slot().current_level;
+    let level_data = level_assets.get(&levels.0[&level]).unwrap();
+
+    let Ok(replay) = Replay::load(&Replay::best_path(level)) else {
+        return;
+    };
+
+    // A ghost recorded on a different layout would run through thin air
+    if replay.level_hash != level_data.hash || replay.frames.is_empty() {
+        return;
+    }
+
+    // Frames come from the recording rather than the animation's own timing
+    let mut animation = SpriteAnimation::new(game_assets.player_animations.clone(), "idle");
+    animation.speed = 0.;
+
+    commands.spawn((
+        SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                color: Color::WHITE.with_a(0.4),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: Vec3::new(0., 0., -0.1),
+                scale: Vec3::ONE * tuning.sprite_scale,
+                ..Default::default()
+            },
+            visibility: if settings.ghost {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            },
+            ..Default::default()
+        },
+        animation,
+        Ghost {
+            frames: replay.frames,
+            tick: 0,
+        },
+    ));
+}
+
+fn despawn_ghost(mut commands: Commands, query: Query<Entity, With<Ghost>>) {
+    if let Ok(entity) = query.get_single() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn ghost_visibility_system(
+    settings: Res<Settings>,
+    mut query: Query<&mut Visibility, With<Ghost>>,
+) {
+    if let Ok(mut visibility) = query.get_single_mut() {
+        *visibility = if settings.ghost {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// Advances the ghost one recorded frame per tick, in step with the live player. It stays on
+/// its last frame once the recorded run has finished.
+fn ghost_movement_system(
+    player_query: Query<(), With<Player>>,
+    mut ghost_query: Query<(
+        &mut Ghost,
+        &mut Transform,
+        &mut SpriteAnimation,
+        &mut TextureAtlasSprite,
+    )>,
+) {
+    if player_query.is_empty() {
+        return;
+    }
+
+    if let Ok((mut ghost, mut tf, mut animation, mut sprite)) = ghost_query.get_single_mut() {
+        let Some(frame) = ghost.frames.get(ghost.tick).copied() else {
+            return;
+        };
+
+        ghost.tick += 1;
+
+        tf.translation = Vec2::from_array(frame.position).extend(tf.translation.z);
+        tf.scale = Vec3::splat(frame.scale);
+        sprite.flip_x = frame.flip_x;
+        animation.play(frame.state.animation());
+        animation.frame = frame.sprite_index as usize;
+    }
+}
@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+
+use super::{
+    animation::SpriteAnimation, camera::camera_follow_system, intro::intro_system,
+    patient::Patient, pill::Pill,
+};
+use crate::{GameAssets, GameState, MainCamera, UIAssets};
+
+/// Distance kept between indicators and the edge of the screen.
+const EDGE_MARGIN: f32 = 40.;
+const ARROW_DISTANCE: f32 = 28.;
+const ICON_SCALE: f32 = 1.5;
+
+/// Points towards a pill or the patient while it is off screen.
+#[derive(Component)]
+struct Indicator(Entity);
+
+#[derive(Component)]
+struct IndicatorArrow;
+
+pub struct IndicatorPlugin;
+
+impl Plugin for IndicatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(GameState::Level), despawn_indicators)
+            .add_systems(
+                Update,
+                (
+                    spawn_indicators,
+                    indicator_system
+                        .after(camera_follow_system)
+                        .after(intro_system),
+                )
+                    .run_if(in_state(GameState::Level)),
+            );
+    }
+}
+
+fn spawn_indicators(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    ui_assets: Res<UIAssets>,
+    pill_query: Query<(Entity, &Pill), Added<Pill>>,
+    patient_query: Query<Entity, Added<Patient>>,
+) {
+    let targets = pill_query
+        .iter()
+        .map(|(entity, pill)| {
+            let color = if pill.bonus {
+                Color::GOLD
+            } else {
+                Color::WHITE
+            };
+            (entity, game_assets.pill_animations.clone(), "spin", color)
+        })
+        .chain(patient_query.iter().map(|entity| {
+            (
+                entity,
+                game_assets.patient_animations.clone(),
+                "healthy",
+                Color::WHITE,
+            )
+        }));
+
+    for (target, library, animation, color) in targets {
+        commands
+            .spawn((
+                SpatialBundle {
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+                Indicator(target),
+            ))
+            .with_children(|n| {
+                n.spawn((
+                    SpriteSheetBundle {
+                        sprite: TextureAtlasSprite {
+                            color,
+                            ..Default::default()
+                        },
+                        transform: Transform::from_scale(Vec3::ONE * ICON_SCALE),
+                        ..Default::default()
+                    },
+                    SpriteAnimation::new(library, animation),
+                ));
+                n.spawn((
+                    Text2dBundle {
+                        text: Text::from_section(
+                            ">",
+                            TextStyle {
+                                font: ui_assets.font.clone(),
+                                font_size: 40.,
+                                color: Color::BLACK,
+                            },
+                        ),
+                        ..Default::default()
+                    },
+                    IndicatorArrow,
+                ));
+            });
+    }
+}
+
+fn despawn_indicators(mut commands: Commands, query: Query<Entity, With<Indicator>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn indicator_system(
+    mut commands: Commands,
+    camera_query: Query<
+        (&Transform, &OrthographicProjection),
+        (
+            With<MainCamera>,
+            Without<Indicator>,
+            Without<IndicatorArrow>,
+        ),
+    >,
+    target_query: Query<&Transform, (Without<Indicator>, Without<IndicatorArrow>)>,
+    mut indicator_query: Query<(
+        Entity,
+        &Indicator,
+        &mut Transform,
+        &mut Visibility,
+        &Children,
+    )>,
+    mut arrow_query: Query<&mut Transform, (With<IndicatorArrow>, Without<Indicator>)>,
+) {
+    let Ok((camera_tf, projection)) = camera_query.get_single() else {
+        return;
+    };
+
+    let center = camera_tf.translation.truncate();
+    let half_view = projection.area.half_size();
+
+    for (entity, indicator, mut tf, mut visibility, children) in indicator_query.iter_mut() {
+        // Pills are despawned when collected
+        let Ok(target_tf) = target_query.get(indicator.0) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        let offset = target_tf.translation.truncate() - center;
+        if offset.x.abs() < half_view.x && offset.y.abs() < half_view.y {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        // Scales the offset down until it touches the inset edge of the screen
+        let inset = (half_view - EDGE_MARGIN).max(Vec2::ZERO);
+        let scale = (inset / offset.abs()).min_element();
+        tf.translation = (center + offset * scale).extend(10.);
+        *visibility = Visibility::Inherited;
+
+        let direction = offset.normalize_or_zero();
+        for child in children.iter() {
+            if let Ok(mut arrow_tf) = arrow_query.get_mut(*child) {
+                arrow_tf.translation = (direction * ARROW_DISTANCE).extend(0.);
+                arrow_tf.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+            }
+        }
+    }
+}
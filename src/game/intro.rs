@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    camera::clamp_to_bounds,
+    player::{Abilities, SpawnPlayerEvent},
+    replay::{jump_buffer_system, JumpBuffer},
+    LevelBounds,
+};
+use crate::{pause_menu::PauseState, GameState, MainCamera};
+
+/// Camera pan shown before a level starts, from the patient through every pill and back to
+/// where the player spawns.
+#[derive(Deserialize, Clone, Copy)]
+pub struct IntroData {
+    /// Pixels per second.
+    pan_speed: f32,
+    /// Seconds spent looking at each stop.
+    hold: f32,
+}
+
+/// Present while the intro is playing. The player spawns and the stopwatch starts once it ends.
+#[derive(Resource)]
+pub struct LevelIntro {
+    stops: Vec<Vec2>,
+    next_stop: usize,
+    position: Vec2,
+    pan_speed: f32,
+    hold_timer: Timer,
+    abilities: Abilities,
+}
+
+impl LevelIntro {
+    pub fn new(data: IntroData, stops: Vec<Vec2>, abilities: Abilities) -> Self {
+        Self {
+            position: stops.first().copied().unwrap_or_default(),
+            stops,
+            next_stop: 0,
+            pan_speed: data.pan_speed,
+            hold_timer: Timer::from_seconds(data.hold, TimerMode::Once),
+            abilities,
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.next_stop >= self.stops.len()
+    }
+
+    fn advance(&mut self, delta: std::time::Duration) {
+        let Some(stop) = self.stops.get(self.next_stop).copied() else {
+            return;
+        };
+
+        let step = self.pan_speed * delta.as_secs_f32();
+        if self.position.distance(stop) > step {
+            self.position += (stop - self.position).normalize() * step;
+            return;
+        }
+
+        self.position = stop;
+        if self.hold_timer.tick(delta).finished() {
+            self.hold_timer.reset();
+            self.next_stop += 1;
+        }
+    }
+}
+
+pub struct IntroPlugin;
+
+impl Plugin for IntroPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(GameState::Level), remove_intro)
+            .add_systems(
+                Update,
+                intro_system
+                    .after(jump_buffer_system)
+                    .run_if(resource_exists::<LevelIntro>())
+                    .run_if(in_state(GameState::Level).and_then(in_state(PauseState::Unpaused))),
+            );
+    }
+}
+
+fn remove_intro(mut commands: Commands) {
+    commands.remove_resource::<LevelIntro>();
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn intro_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    kb: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    bounds: Res<LevelBounds>,
+    mut intro: ResMut<LevelIntro>,
+    mut jump_buffer: ResMut<JumpBuffer>,
+    mut player_events: EventWriter<SpawnPlayerEvent>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<MainCamera>>,
+) {
+    // Escape is left out so that pausing does not skip the intro
+    let skipped = kb.get_just_pressed().any(|key| *key != KeyCode::Escape)
+        || mouse.get_just_pressed().next().is_some();
+
+    intro.advance(time.delta());
+
+    if skipped || intro.finished() {
+        // The key that skipped the intro should not also make the player jump
+        jump_buffer.0 = false;
+        player_events.send(SpawnPlayerEvent {
+            abilities: intro.abilities,
+        });
+        commands.remove_resource::<LevelIntro>();
+        return;
+    }
+
+    if let Ok((mut camera_tf, projection)) = camera_query.get_single_mut() {
+        let position = clamp_to_bounds(intro.position, projection.area.half_size(), bounds.0);
+        camera_tf.translation = position.extend(camera_tf.translation.z);
+    }
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use super::{patient::Patient, pill::Pill, player::Player, tuning::GameTuning, LevelData, Levels};
+use crate::{GameData, GameState, Settings};
+
+/// Largest size the minimap is drawn at, in pixels. The level keeps its aspect ratio within it.
+const MINIMAP_SIZE: Vec2 = Vec2::new(220., 140.);
+const MARKER_SIZE: f32 = 6.;
+
+/// Overview of the whole level, shown in a corner of the screen.
+#[derive(Component)]
+struct Minimap {
+    bounds: Rect,
+    /// Minimap pixels per world unit.
+    scale: f32,
+}
+
+impl Minimap {
+    /// Converts a world position to a position on the minimap, measured from its top left.
+    fn to_minimap(&self, position: Vec2) -> Vec2 {
+        Vec2::new(
+            position.x - self.bounds.min.x,
+            self.bounds.max.y - position.y,
+        ) * self.scale
+    }
+}
+
+/// Shows where an entity is on the minimap.
+#[derive(Component)]
+struct MinimapMarker(Entity);
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Level), spawn_minimap)
+            .add_systems(OnExit(GameState::Level), despawn_minimap)
+            .add_systems(
+                Update,
+                (
+                    spawn_minimap_markers,
+                    minimap_marker_system,
+                    minimap_visibility_system.run_if(resource_changed::<Settings>()),
+                )
+                    .run_if(in_state(GameState::Level)),
+            );
+    }
+}
+
+fn spawn_minimap(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    tuning: Res<GameTuning>,
+    game_data: Res<GameData>,
+    level_assets: Res<Assets<LevelData>>,
+    levels: Res<Levels>,
+) {
+    let level_handle = levels.0.get(&game_data.slot().current_level).unwrap();
+    let level_data = level_assets.get(level_handle).unwrap();
+
+    let bounds = level_data.bounds(tuning.camera.bounds_margin);
+    let scale = (MINIMAP_SIZE / bounds.size()).min_element();
+    let minimap = Minimap { bounds, scale };
+    let platforms: Vec<_> = level_data
+        .platforms
+        .iter()
+        .map(|platform| {
+            let size = platform.size(tuning.sprite_scale) * scale;
+            (minimap.to_minimap(platform.position()) - size / 2., size)
+        })
+        .collect();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.),
+                    bottom: Val::Px(10.),
+                    width: Val::Px(bounds.width() * scale),
+                    height: Val::Px(bounds.height() * scale),
+                    ..Default::default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.4).into(),
+                visibility: if settings.minimap {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                },
+                ..Default::default()
+            },
+            minimap,
+        ))
+        .with_children(|n| {
+            for (position, size) in platforms {
+                n.spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(position.x),
+                        top: Val::Px(position.y),
+                        width: Val::Px(size.x.max(2.)),
+                        height: Val::Px(size.y.max(2.)),
+                        ..Default::default()
+                    },
+                    background_color: Color::rgb(0.8, 0.8, 0.8).into(),
+                    ..Default::default()
+                });
+            }
+        });
+}
+
+fn despawn_minimap(mut commands: Commands, query: Query<Entity, With<Minimap>>) {
+    if let Ok(entity) = query.get_single() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn minimap_visibility_system(
+    settings: Res<Settings>,
+    mut query: Query<&mut Visibility, With<Minimap>>,
+) {
+    if let Ok(mut visibility) = query.get_single_mut() {
+        *visibility = if settings.minimap {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn spawn_minimap_markers(
+    mut commands: Commands,
+    minimap_query: Query<Entity, With<Minimap>>,
+    pill_query: Query<(Entity, &Pill), Added<Pill>>,
+    patient_query: Query<Entity, Added<Patient>>,
+    player_query: Query<Entity, Added<Player>>,
+) {
+    let Ok(minimap) = minimap_query.get_single() else {
+        return;
+    };
+
+    let markers = pill_query
+        .iter()
+        .map(|(entity, pill)| {
+            let color = if pill.bonus {
+                Color::GOLD
+            } else {
+                Color::WHITE
+            };
+            (entity, color)
+        })
+        .chain(patient_query.iter().map(|entity| (entity, Color::CRIMSON)))
+        .chain(player_query.iter().map(|entity| (entity, Color::SEA_GREEN)));
+
+    for (target, color) in markers {
+        let marker = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(MARKER_SIZE),
+                        height: Val::Px(MARKER_SIZE),
+                        ..Default::default()
+                    },
+                    background_color: color.into(),
+                    ..Default::default()
+                },
+                MinimapMarker(target),
+            ))
+            .id();
+        commands.entity(minimap).add_child(marker);
+    }
+}
+
+fn minimap_marker_system(
+    mut commands: Commands,
+    minimap_query: Query<&Minimap>,
+    target_query: Query<&Transform>,
+    mut marker_query: Query<(Entity, &MinimapMarker, &mut Style)>,
+) {
+    let Ok(minimap) = minimap_query.get_single() else {
+        return;
+    };
+
+    for (entity, marker, mut style) in marker_query.iter_mut() {
+        let Ok(target_tf) = target_query.get(marker.0) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        let position = minimap.to_minimap(target_tf.translation.truncate()) - MARKER_SIZE / 2.;
+        style.left = Val::Px(position.x);
+        style.top = Val::Px(position.y);
+    }
+}
@@ -1,3 +1,5 @@
+use animation::SpriteAnimationPlugin;
+use background::{BackgroundLayerData, BackgroundPlugin};
 use bevy::{
     asset::{AssetLoader, AsyncReadExt},
     prelude::*,
@@ -5,44 +7,291 @@ use bevy::{
     sprite::collide_aabb::{collide, Collision},
     utils::HashMap,
 };
+use camera::CameraPlugin;
+use clock::{ClockPlugin, SpawnClockEvent};
 use floating_label::{FloatingLabelPlugin, SpawnFloatingLabelEvent};
+use ghost::GhostPlugin;
+use indicator::IndicatorPlugin;
+use intro::{IntroData, IntroPlugin, LevelIntro};
+use minimap::MinimapPlugin;
+use particles::ParticlePlugin;
 use patient::{PatientPlugin, SpawnPatientEvent};
 use pill::{PillPlugin, SpawnPillEvent};
-use platform::{PlatformPlugin, SpawnPlatformEvent};
-use player::PlayerPlugin;
+use platform::{PlatformPlugin, SpawnPlatformEvent, PLATFORM_SIZE};
+use player::{Abilities, Player, PlayerPlugin, SpawnPlayerEvent};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use replay::{ReplayPlayback, ReplayPlugin};
 use serde::Deserialize;
 use side_effects::SideEffectsPlugin;
+use speedrun::SpeedrunPlugin;
+use squash::SquashPlugin;
 use thiserror::Error;
+use tuning::{GameTuning, TuningPlugin};
 
 use crate::{
-    components::{Gravity, RectCollisionShape, Velocity},
-    GameData, GameState, UIAssets,
+    components::{Gravity, Grounded, RectCollisionShape, Surface, Velocity, WallContact},
+    GameData, GameState, SaveGameData, UIAssets,
 };
 
+pub mod animation;
+mod background;
+mod camera;
+mod clock;
 mod floating_label;
+mod ghost;
+mod indicator;
+mod intro;
+mod minimap;
+mod particles;
 mod patient;
 mod pill;
 mod platform;
 mod player;
+pub mod replay;
 mod side_effects;
+pub mod speedrun;
+mod squash;
+mod tuning;
 
-const SPRITE_SCALE: f32 = 3.;
 const FIXED_FREQUENCY: f64 = 60.;
-const GRAVITY: f32 = 50.;
 const MAX_LEVELS: usize = 5;
+const SCORE_PER_SECOND: f32 = 100.;
+const SCORE_PER_PILL: u32 = 500;
+const SCORE_PER_BONUS_PILL: u32 = 1000;
+const TIME_FLASH_SECONDS: f32 = 1.;
+/// How long the patient reacts to being cured or expiring before the level ends.
+const ENDING_SECONDS: f32 = 1.5;
+/// Level fields that change how a level plays. Editing anything else, such as the intro or
+/// backgrounds, leaves existing replays and ghosts usable.
+const GAMEPLAY_FIELDS: [&str; 7] = [
+    "platforms",
+    "pills",
+    "clocks",
+    "goal",
+    "abilities",
+    "time_limit",
+    "pill_goal",
+];
 
 #[derive(Resource, Asset, TypePath)]
-struct Levels(HashMap<usize, Handle<LevelData>>);
+pub struct Levels(pub HashMap<usize, Handle<LevelData>>);
 
 #[derive(Deserialize, TypeUuid, Asset, TypePath)]
 #[uuid = "2b2bea01-bf6b-475d-90d6-ccaae422666f"]
-struct LevelData {
-    platforms: Vec<Vec2>,
-    pills: Vec<Vec2>,
+pub struct LevelData {
+    platforms: Vec<PlatformData>,
+    pub pills: Vec<PillData>,
     labels: Vec<(String, Vec2)>,
     time_limit: u64,
     pill_goal: u32,
     goal: Vec2,
+    /// Pickups that add (or with negative seconds, take away) time.
+    #[serde(default)]
+    clocks: Vec<ClockData>,
+    #[serde(default)]
+    abilities: Abilities,
+    /// Scores needed for each star, in ascending order.
+    #[serde(default)]
+    star_scores: Vec<u32>,
+    #[serde(default)]
+    intro: Option<IntroData>,
+    #[serde(default)]
+    backgrounds: Vec<BackgroundLayerData>,
+    /// Identifies the gameplay layout a replay was recorded on.
+    #[serde(skip)]
+    pub hash: u64,
+}
+
+impl LevelData {
+    fn bonus_pills(&self) -> u32 {
+        self.pills.iter().filter(|pill| pill.is_bonus()).count() as u32
+    }
+
+    fn score(&self, time_remaining: f32, pills_collected: u32, bonus_collected: u32) -> u32 {
+        (time_remaining * SCORE_PER_SECOND) as u32
+            + pills_collected * SCORE_PER_PILL
+            + bonus_collected * SCORE_PER_BONUS_PILL
+    }
+
+    fn bounds(&self, margin: f32) -> Rect {
+        let positions = self
+            .platforms
+            .iter()
+            .map(|platform| platform.position())
+            .chain(self.pills.iter().map(|pill| pill.position()))
+            .chain(self.clocks.iter().map(|clock| clock.position))
+            .chain([Vec2::ZERO, self.goal]);
+
+        let (min, max) = positions.fold((Vec2::MAX, Vec2::MIN), |(min, max), position| {
+            (min.min(position), max.max(position))
+        });
+
+        Rect::from_corners(min - margin, max + margin)
+    }
+
+    fn stars(&self, score: u32) -> u8 {
+        self.star_scores.iter().filter(|s| score >= **s).count() as u8
+    }
+}
+
+/// Time left to finish the current level, which pickups and side effects can change.
+#[derive(Resource)]
+pub struct Stopwatch {
+    time_limit: f32,
+    elapsed: f32,
+    adjustment: f32,
+}
+
+impl Stopwatch {
+    fn new(time_limit: f32) -> Self {
+        Self {
+            time_limit,
+            elapsed: 0.,
+            adjustment: 0.,
+        }
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    pub fn remaining(&self) -> f32 {
+        (self.time_limit + self.adjustment - self.elapsed).max(0.)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.remaining() <= 0.
+    }
+
+    /// Fraction of the original time limit that is left.
+    fn remaining_fraction(&self) -> f32 {
+        self.remaining() / self.time_limit
+    }
+
+    fn tick(&mut self, delta: f32) {
+        self.elapsed += delta;
+    }
+
+    fn adjust(&mut self, seconds: f32) {
+        self.adjustment += seconds;
+    }
+}
+
+/// Inserted once the level has been won or lost. Play stops while the patient reacts, and then
+/// the game moves on to `next_state`. Until then the level cannot be left, so that the outcome
+/// cannot change.
+#[derive(Resource)]
+pub struct LevelEnding {
+    next_state: GameState,
+    timer: Timer,
+}
+
+impl LevelEnding {
+    fn new(next_state: GameState) -> Self {
+        Self {
+            next_state,
+            timer: Timer::from_seconds(ENDING_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+/// Area that the camera is kept within, enclosing everything in the level.
+#[derive(Resource)]
+pub struct LevelBounds(pub Rect);
+
+/// Adds seconds to the stopwatch, or takes them away when negative.
+#[derive(Event)]
+pub struct AdjustTimeEvent(pub f32);
+
+/// Seed for everything random in the current level attempt, such as pill side effects.
+#[derive(Resource)]
+pub struct LevelSeed(pub u64);
+
+/// Outcome of the most recently completed level, shown on the results screen.
+#[derive(Resource)]
+pub struct LevelResult {
+    pub level: usize,
+    pub time: f32,
+    pub pills_collected: u32,
+    pub bonus_collected: u32,
+    pub bonus_total: u32,
+    pub score: u32,
+    pub stars: u8,
+    pub max_stars: u8,
+    pub new_best: bool,
+    pub leaderboard_rank: Option<usize>,
+    /// Whether this was a replay being played back rather than a real attempt.
+    pub replay: bool,
+}
+
+#[derive(Deserialize)]
+struct ClockData {
+    position: Vec2,
+    seconds: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PlatformData {
+    Position(Vec2),
+    WithOptions {
+        position: Vec2,
+        #[serde(default)]
+        surface: Surface,
+        /// Size in world units, for walls and longer floors. Defaults to a single platform sprite.
+        #[serde(default)]
+        size: Option<Vec2>,
+    },
+}
+
+/// Bonus pills are not needed to finish a level, but add to the score.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum PillData {
+    Position(Vec2),
+    WithOptions {
+        position: Vec2,
+        #[serde(default)]
+        bonus: bool,
+    },
+}
+
+impl PlatformData {
+    fn position(&self) -> Vec2 {
+        match self {
+            PlatformData::Position(position) | PlatformData::WithOptions { position, .. } => {
+                *position
+            }
+        }
+    }
+
+    fn size(&self, sprite_scale: f32) -> Vec2 {
+        match self {
+            PlatformData::WithOptions {
+                size: Some(size), ..
+            } => *size,
+            _ => PLATFORM_SIZE * sprite_scale,
+        }
+    }
+
+    fn surface(&self) -> Surface {
+        match self {
+            PlatformData::Position(_) => Surface::Normal,
+            PlatformData::WithOptions { surface, .. } => *surface,
+        }
+    }
+}
+
+impl PillData {
+    fn position(&self) -> Vec2 {
+        match self {
+            PillData::Position(position) | PillData::WithOptions { position, .. } => *position,
+        }
+    }
+
+    fn is_bonus(&self) -> bool {
+        matches!(self, PillData::WithOptions { bonus: true, .. })
+    }
 }
 
 #[derive(Default)]
@@ -50,18 +299,21 @@ struct LevelDataLoader;
 
 #[non_exhaustive]
 #[derive(Debug, Error)]
-enum LevelDataLoaderError {
+enum JsonLoaderError {
     #[error("Could not load asset: {0}")]
     Io(#[from] std::io::Error),
 
     #[error("Could not parse JSON: {0}")]
     JsonParseError(#[from] serde_json::error::Error),
+
+    #[error("Invalid animation \"{0}\": {1}")]
+    InvalidAnimation(String, &'static str),
 }
 
 impl AssetLoader for LevelDataLoader {
     type Asset = LevelData;
     type Settings = ();
-    type Error = LevelDataLoaderError;
+    type Error = JsonLoaderError;
 
     fn extensions(&self) -> &[&str] {
         &["json"]
@@ -76,43 +328,103 @@ impl AssetLoader for LevelDataLoader {
         Box::pin(async move {
             let mut bytes = Vec::new();
             reader.read_to_end(&mut bytes).await?;
-            let data = serde_json::from_slice(&bytes)?;
+            let mut data: LevelData = serde_json::from_slice(&bytes)?;
+            data.hash = gameplay_hash(&bytes)?;
             Ok(data)
         })
     }
 }
 
+/// Hashes only the `GAMEPLAY_FIELDS` of a level file.
+fn gameplay_hash(bytes: &[u8]) -> Result<u64, serde_json::Error> {
+    let level: serde_json::Value = serde_json::from_slice(bytes)?;
+    let gameplay: serde_json::Map<_, _> = GAMEPLAY_FIELDS
+        .iter()
+        .filter_map(|field| Some((field.to_string(), level.get(field)?.clone())))
+        .collect();
+
+    Ok(replay::level_hash(&serde_json::to_vec(&gameplay)?))
+}
+
 #[derive(Component)]
-struct HUD;
+struct Hud;
 
 #[derive(Component)]
 struct CollectedLabel;
 
 #[derive(Component)]
-struct StopwatchLabel(Timer);
+struct BonusLabel;
+
+#[derive(Component)]
+struct StopwatchLabel;
+
+/// Briefly shows how much time was just added or taken away.
+#[derive(Component)]
+struct TimeDeltaLabel(Timer);
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(PlayerPlugin)
+        app.add_plugins(SpriteAnimationPlugin)
+            .add_plugins(PlayerPlugin)
             .add_plugins(PlatformPlugin)
             .add_plugins(PillPlugin)
             .add_plugins(FloatingLabelPlugin)
             .add_plugins(PatientPlugin)
             .add_plugins(SideEffectsPlugin)
+            .add_plugins(TuningPlugin)
+            .add_plugins(ReplayPlugin)
+            .add_plugins(GhostPlugin)
+            .add_plugins(SpeedrunPlugin)
+            .add_plugins(ClockPlugin)
+            .add_plugins(CameraPlugin)
+            .add_plugins(IntroPlugin)
+            .add_plugins(IndicatorPlugin)
+            .add_plugins(MinimapPlugin)
+            .add_plugins(BackgroundPlugin)
+            .add_plugins(ParticlePlugin)
+            .add_plugins(SquashPlugin)
+            .add_event::<AdjustTimeEvent>()
             .init_asset::<LevelData>()
             .init_asset_loader::<LevelDataLoader>()
             .add_systems(Startup, load_level_data)
             .add_systems(OnEnter(GameState::Level), (spawn_world, spawn_hud))
-            .add_systems(OnExit(GameState::Level), despawn_hud)
+            .add_systems(OnExit(GameState::Level), (despawn_hud, remove_level_ending))
+            .add_systems(OnEnter(GameState::Restarting), restart_level_system)
+            .add_systems(
+                OnEnter(GameState::LevelCompleted),
+                record_completion.run_if(|result: Res<LevelResult>| !result.replay),
+            )
             .add_systems(
                 FixedUpdate,
-                (gravity_system, velocity_system, collision_system)
-                    .chain()
+                (
+                    (gravity_system, velocity_system, collision_system).chain(),
+                    // Ticks with the level so that replays run out of time on the same tick
+                    (
+                        adjust_time_system.run_if(on_event::<AdjustTimeEvent>()),
+                        stopwatch_system.run_if(not(resource_exists::<LevelEnding>())),
+                    )
+                        .chain()
+                        .after(collision_system),
+                )
                     .run_if(in_state(GameState::Level)),
             )
-            .add_systems(Update, stopwatch_system.run_if(in_state(GameState::Level)))
+            .add_systems(
+                Update,
+                (
+                    stopwatch_label_system,
+                    time_delta_label_system,
+                    level_ending_system.run_if(resource_exists::<LevelEnding>()),
+                )
+                    .run_if(in_state(GameState::Level)),
+            )
+            .add_systems(
+                Update,
+                restart_hotkey_system
+                    .run_if(in_state(GameState::Level).or_else(in_state(GameState::GameOver)))
+                    .run_if(not(resource_exists::<LevelEnding>())),
+            )
             .insert_resource(Time::<Fixed>::from_hz(FIXED_FREQUENCY));
     }
 }
@@ -128,28 +440,51 @@ fn load_level_data(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(Levels(levels));
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_world(
+    mut commands: Commands,
     mut platform_events: EventWriter<SpawnPlatformEvent>,
     mut pill_events: EventWriter<SpawnPillEvent>,
     mut label_events: EventWriter<SpawnFloatingLabelEvent>,
     mut patient_events: EventWriter<SpawnPatientEvent>,
+    mut clock_events: EventWriter<SpawnClockEvent>,
+    mut player_events: EventWriter<SpawnPlayerEvent>,
+    tuning: Res<GameTuning>,
     game_data: Res<GameData>,
     level_assets: Res<Assets<LevelData>>,
     levels: Res<Levels>,
+    playback: Option<Res<ReplayPlayback>>,
 ) {
-    let level_handle = levels.0.get(&game_data.current_level).unwrap();
+    let level_handle = levels.0.get(&game_data.slot().current_level).unwrap();
     let level_data = level_assets.get(level_handle).unwrap();
 
     platform_events.send_batch(
         level_data
             .platforms
             .iter()
-            .map(|pos| SpawnPlatformEvent(*pos)),
+            .map(|platform| SpawnPlatformEvent {
+                position: platform.position(),
+                size: platform.size(tuning.sprite_scale),
+                surface: platform.surface(),
+            }),
     );
 
-    pill_events.send_batch(level_data.pills.iter().map(|pos| SpawnPillEvent {
-        position: *pos,
-        side_effect: rand::random(),
+    // Replays reuse the seed they were recorded with so that side effects come out the same
+    let seed = playback
+        .as_ref()
+        .map_or_else(rand::random, |playback| playback.seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+    commands.insert_resource(LevelSeed(seed));
+
+    pill_events.send_batch(level_data.pills.iter().map(|pill| SpawnPillEvent {
+        position: pill.position(),
+        side_effect: rng.gen(),
+        bonus: pill.is_bonus(),
+    }));
+
+    clock_events.send_batch(level_data.clocks.iter().map(|clock| SpawnClockEvent {
+        position: clock.position,
+        seconds: clock.seconds,
     }));
 
     label_events.send_batch(
@@ -160,6 +495,24 @@ fn spawn_world(
     );
 
     patient_events.send(SpawnPatientEvent(level_data.goal));
+    commands.insert_resource(Stopwatch::new(level_data.time_limit as f32));
+    commands.insert_resource(LevelBounds(level_data.bounds(tuning.camera.bounds_margin)));
+
+    // Replays go straight into the level, as the intro could have been skipped at any point
+    match level_data.intro {
+        Some(intro) if playback.is_none() => {
+            let stops = std::iter::once(level_data.goal)
+                .chain(level_data.pills.iter().map(|pill| pill.position()))
+                .chain([Vec2::ZERO])
+                .collect();
+            commands.insert_resource(LevelIntro::new(intro, stops, level_data.abilities));
+        }
+        _ => {
+            player_events.send(SpawnPlayerEvent {
+                abilities: level_data.abilities,
+            });
+        }
+    }
 }
 
 fn spawn_hud(
@@ -169,7 +522,7 @@ fn spawn_hud(
     level_assets: Res<Assets<LevelData>>,
     levels: Res<Levels>,
 ) {
-    let level_handle = levels.0.get(&game_data.current_level).unwrap();
+    let level_handle = levels.0.get(&game_data.slot().current_level).unwrap();
     let level_data = level_assets.get(level_handle).unwrap();
 
     commands
@@ -185,7 +538,7 @@ fn spawn_hud(
                 },
                 ..Default::default()
             },
-            HUD,
+            Hud,
         ))
         .with_children(|hud| {
             hud.spawn(NodeBundle {
@@ -214,39 +567,151 @@ fn spawn_hud(
                     CollectedLabel,
                 ));
 
+                let bonus_pills = level_data.bonus_pills();
+
+                if bonus_pills > 0 {
+                    top_row.spawn((
+                        TextBundle::from_sections([
+                            TextSection::new("Bonus: ", style.clone()),
+                            TextSection::new("0", style.clone()),
+                            TextSection::new(format!("/{}", bonus_pills), style.clone()),
+                        ]),
+                        BonusLabel,
+                    ));
+                }
+
                 top_row.spawn((
                     TextBundle::from_sections([
                         TextSection::new("Time Left: ", style.clone()),
                         TextSection::new(level_data.time_limit.to_string(), style.clone()),
                         TextSection::new("s", style.clone()),
                     ]),
-                    StopwatchLabel(Timer::new(
-                        std::time::Duration::from_secs(level_data.time_limit),
-                        TimerMode::Once,
-                    )),
+                    StopwatchLabel,
+                ));
+
+                top_row.spawn((
+                    TextBundle::from_section("", style.clone()),
+                    TimeDeltaLabel(Timer::from_seconds(TIME_FLASH_SECONDS, TimerMode::Once)),
                 ));
             });
         });
 }
 
-fn despawn_hud(mut commands: Commands, query: Query<Entity, With<HUD>>) {
+fn despawn_hud(mut commands: Commands, query: Query<Entity, With<Hud>>) {
     if let Ok(entity) = query.get_single() {
         commands.entity(entity).despawn_recursive();
     }
 }
 
 fn stopwatch_system(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    mut stopwatch: ResMut<Stopwatch>,
+    player_query: Query<(), With<Player>>,
+) {
+    // The stopwatch starts with the player, after any intro
+    if player_query.is_empty() {
+        return;
+    }
+
+    stopwatch.tick(time.delta_seconds());
+
+    if stopwatch.finished() {
+        commands.insert_resource(LevelEnding::new(GameState::GameOver));
+    }
+}
+
+fn stopwatch_label_system(
+    stopwatch: Res<Stopwatch>,
+    mut query: Query<&mut Text, With<StopwatchLabel>>,
+) {
+    if let Ok(mut text) = query.get_single_mut() {
+        text.sections[1].value = (stopwatch.remaining() as u64).to_string();
+    }
+}
+
+fn level_ending_system(
     time: Res<Time>,
     mut game_state: ResMut<NextState<GameState>>,
-    mut query: Query<(&mut Text, &mut StopwatchLabel)>,
+    mut ending: ResMut<LevelEnding>,
+) {
+    if ending.timer.tick(time.delta()).just_finished() {
+        game_state.set(ending.next_state.clone());
+    }
+}
+
+/// Records the result in the save slot when the results screen is reached. Watching a replay
+/// does not count as completing the level again, so this only runs for real attempts.
+pub fn record_completion(
+    mut save_events: EventWriter<SaveGameData>,
+    mut game_data: ResMut<GameData>,
+    mut result: ResMut<LevelResult>,
 ) {
-    if let Ok((mut text, mut stopwatch)) = query.get_single_mut() {
-        stopwatch.0.tick(time.delta());
-        text.sections[1].value = stopwatch.0.remaining().as_secs().to_string();
+    result.new_best = game_data.slot_mut().record_completion(
+        result.time,
+        result.pills_collected + result.bonus_collected,
+        result.score,
+        result.stars,
+    );
+
+    save_events.send_default();
+}
 
-        if stopwatch.0.finished() {
-            game_state.set(GameState::GameOver);
+fn remove_level_ending(mut commands: Commands) {
+    commands.remove_resource::<LevelEnding>();
+}
+
+fn adjust_time_system(
+    mut events: EventReader<AdjustTimeEvent>,
+    mut stopwatch: ResMut<Stopwatch>,
+    mut query: Query<(&mut Text, &mut TimeDeltaLabel)>,
+) {
+    let seconds: f32 = events.read().map(|event| event.0).sum();
+    stopwatch.adjust(seconds);
+
+    if let Ok((mut text, mut flash)) = query.get_single_mut() {
+        text.sections[0].value = format!("{:+}s", seconds);
+        text.sections[0].style.color = if seconds < 0. {
+            Color::CRIMSON
+        } else {
+            Color::SEA_GREEN
+        };
+        flash.0.reset();
+    }
+}
+
+fn time_delta_label_system(time: Res<Time>, mut query: Query<(&mut Text, &mut TimeDeltaLabel)>) {
+    if let Ok((mut text, mut flash)) = query.get_single_mut() {
+        if flash.0.finished() {
+            return;
         }
+
+        flash.0.tick(time.delta());
+
+        // Blinks a few times before fading out
+        let alpha = if flash.0.finished() {
+            0.
+        } else {
+            let blink = ((flash.0.elapsed_secs() * 8.) as u32).is_multiple_of(2);
+            if blink {
+                1.
+            } else {
+                0.3
+            }
+        };
+        text.sections[0].style.color.set_a(alpha);
+    }
+}
+
+/// Leaving `GameState::Level` runs all the despawn systems, and coming straight back respawns
+/// the level from scratch.
+fn restart_level_system(mut game_state: ResMut<NextState<GameState>>) {
+    game_state.set(GameState::Level);
+}
+
+fn restart_hotkey_system(kb: Res<Input<KeyCode>>, mut game_state: ResMut<NextState<GameState>>) {
+    if kb.just_pressed(KeyCode::R) {
+        game_state.set(GameState::Restarting);
     }
 }
 
@@ -256,18 +721,35 @@ fn velocity_system(time: Res<Time<Fixed>>, mut query: Query<(&mut Transform, &Ve
     }
 }
 
-fn gravity_system(mut query: Query<(&mut Velocity, &Gravity)>) {
+fn gravity_system(tuning: Res<GameTuning>, mut query: Query<(&mut Velocity, &Gravity)>) {
     for (mut velocity, gravity) in query.iter_mut() {
-        velocity.0 += gravity.0 * GRAVITY;
+        velocity.0 += gravity.0 * tuning.gravity;
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn collision_system(
-    mut movable_query: Query<(&mut Transform, &mut Velocity, &RectCollisionShape)>,
-    static_query: Query<(&Transform, &RectCollisionShape), Without<Velocity>>,
+    mut movable_query: Query<(
+        &mut Transform,
+        &mut Velocity,
+        &RectCollisionShape,
+        Option<&mut Grounded>,
+        Option<&mut WallContact>,
+    )>,
+    static_query: Query<(&Transform, &RectCollisionShape, Option<&Surface>), Without<Velocity>>,
 ) {
-    for (mut movable_tf, mut movable_vel, movable_col) in movable_query.iter_mut() {
-        for (static_tf, static_col) in static_query.iter() {
+    for (mut movable_tf, mut movable_vel, movable_col, mut grounded, mut wall_contact) in
+        movable_query.iter_mut()
+    {
+        if let Some(grounded) = grounded.as_mut() {
+            grounded.0 = None;
+        }
+
+        if let Some(wall_contact) = wall_contact.as_mut() {
+            wall_contact.0 = None;
+        }
+
+        for (static_tf, static_col, surface) in static_query.iter() {
             if !(movable_col.collide && static_col.collide) {
                 continue;
             }
@@ -284,6 +766,10 @@ fn collision_system(
                     movable_vel.0.y = 0.;
                     movable_tf.translation.y =
                         static_tf.translation.y + (static_col.size.y + movable_col.size.y) / 2.;
+
+                    if let Some(grounded) = grounded.as_mut() {
+                        grounded.0 = Some(surface.copied().unwrap_or_default());
+                    }
                 }
                 Some(Collision::Bottom) => {
                     movable_vel.0.y = 0.;
@@ -294,14 +780,178 @@ fn collision_system(
                     movable_vel.0.x = 0.;
                     movable_tf.translation.x =
                         static_tf.translation.x - (static_col.size.x + movable_col.size.x) / 2.;
+
+                    if let Some(wall_contact) = wall_contact.as_mut() {
+                        wall_contact.0 = Some(1.);
+                    }
                 }
                 Some(Collision::Right) => {
                     movable_vel.0.x = 0.;
                     movable_tf.translation.x =
                         static_tf.translation.x + (static_col.size.x + movable_col.size.x) / 2.;
+
+                    if let Some(wall_contact) = wall_contact.as_mut() {
+                        wall_contact.0 = Some(-1.);
+                    }
                 }
                 None => {}
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::LoadState;
+    use bevy_kira_audio::AudioChannel;
+
+    use super::*;
+    use crate::{
+        pause_menu::PauseState, AudioAssets, GameAssets, SFXChannel, SaveGameData, SaveSlot,
+        Settings,
+    };
+
+    fn test_app() -> App {
+        let mut app = App::new();
+
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Image>()
+            .init_asset::<Font>()
+            .init_asset::<TextureAtlas>()
+            .init_asset::<bevy_kira_audio::AudioSource>()
+            .add_state::<GameState>()
+            .add_state::<PauseState>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<AudioChannel<SFXChannel>>()
+            .add_event::<SaveGameData>()
+            .init_resource::<Settings>()
+            .insert_resource(GameData {
+                slots: vec![SaveSlot::new("Test")],
+                active_slot: 0,
+            })
+            .add_plugins(GamePlugin);
+
+        app.insert_resource(UIAssets {
+            font: Handle::default(),
+            button: Handle::default(),
+            button_pressed: Handle::default(),
+        })
+        .insert_resource(GameAssets {
+            player_animations: Handle::default(),
+            platform: Handle::default(),
+            pill_animations: Handle::default(),
+            patient_animations: Handle::default(),
+        })
+        .insert_resource(AudioAssets {
+            bg_music: Handle::default(),
+            player_jump: Handle::default(),
+            pill_collect: Handle::default(),
+            footstep: Handle::default(),
+        });
+
+        app
+    }
+
+    fn wait_for_assets(app: &mut App) {
+        for _ in 0..1000 {
+            app.update();
+
+            let asset_server = app.world.resource::<AssetServer>();
+            let levels = app.world.resource::<Levels>();
+            let level_loaded = levels
+                .0
+                .get(&1)
+                .map(|handle| asset_server.load_state(handle) == LoadState::Loaded)
+                .unwrap_or(false);
+
+            if level_loaded {
+                return;
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        panic!("Level assets did not load");
+    }
+
+    fn run_frames(app: &mut App, frames: usize) {
+        for _ in 0..frames {
+            app.update();
+        }
+    }
+
+    #[test]
+    fn level_hash_ignores_cosmetic_fields() {
+        let level = br#"{"platforms": [[0, 0]], "pills": [[50, 0]], "labels": [],
+            "time_limit": 30, "pill_goal": 1, "goal": [100, 0]}"#;
+        let decorated = br#"{"platforms": [[0, 0]], "pills": [[50, 0]],
+            "labels": [["Jump!", [0, 50]]], "time_limit": 30, "pill_goal": 1, "goal": [100, 0],
+            "intro": {"pan_speed": 400, "hold": 0.5}, "backgrounds": []}"#;
+        let moved_goal = br#"{"platforms": [[0, 0]], "pills": [[50, 0]], "labels": [],
+            "time_limit": 30, "pill_goal": 1, "goal": [120, 0]}"#;
+
+        let hash = gameplay_hash(level).unwrap();
+
+        assert_eq!(gameplay_hash(decorated).unwrap(), hash);
+        assert_ne!(gameplay_hash(moved_goal).unwrap(), hash);
+    }
+
+    #[test]
+    fn completed_level_cannot_turn_into_game_over() {
+        let mut app = test_app();
+        wait_for_assets(&mut app);
+
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Level);
+        run_frames(&mut app, 5);
+
+        app.world
+            .insert_resource(LevelEnding::new(GameState::LevelCompleted));
+
+        // Fall out of the level and press restart while the patient is still reacting
+        let mut player_query = app.world.query_filtered::<&mut Transform, With<Player>>();
+        player_query.single_mut(&mut app.world).translation.y = -10000.;
+        app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::R);
+        run_frames(&mut app, 5);
+
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().get(),
+            GameState::Level
+        );
+        assert_eq!(
+            app.world.resource::<LevelEnding>().next_state,
+            GameState::LevelCompleted
+        );
+    }
+
+    #[test]
+    fn restart_does_not_leak_entities() {
+        let mut app = test_app();
+        wait_for_assets(&mut app);
+
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Level);
+        run_frames(&mut app, 5);
+
+        let entity_count = app.world.entities().len();
+
+        for _ in 0..3 {
+            app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::R);
+            app.update();
+
+            let mut kb = app.world.resource_mut::<Input<KeyCode>>();
+            kb.release(KeyCode::R);
+            kb.clear();
+
+            run_frames(&mut app, 5);
+
+            assert_eq!(
+                *app.world.resource::<State<GameState>>().get(),
+                GameState::Level
+            );
+            assert_eq!(app.world.entities().len(), entity_count);
+        }
+    }
+}
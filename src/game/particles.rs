@@ -0,0 +1,227 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use rand::Rng;
+
+use crate::GameState;
+
+/// How particles from an emitter look and move.
+#[derive(Clone)]
+pub struct ParticleSettings {
+    /// Seconds before a particle disappears.
+    pub lifetime: f32,
+    /// Average starting velocity.
+    pub velocity: Vec2,
+    /// Largest random amount added to or taken from each axis of the starting velocity.
+    pub spread: Vec2,
+    pub gravity: Vec2,
+    pub size: f32,
+    /// Particles start at a random point up to this far from the emitter.
+    pub radius: f32,
+    pub start_color: Color,
+    /// Color at the end of a particle's life, blended towards from `start_color`.
+    pub end_color: Color,
+}
+
+impl ParticleSettings {
+    pub fn dust() -> Self {
+        Self {
+            lifetime: 0.35,
+            velocity: Vec2::new(0., 30.),
+            spread: Vec2::new(40., 12.),
+            gravity: Vec2::ZERO,
+            size: 6.,
+            radius: 6.,
+            start_color: Color::rgba(0.6, 0.55, 0.5, 0.6),
+            end_color: Color::rgba(0.6, 0.55, 0.5, 0.),
+        }
+    }
+
+    pub fn sparkle(color: Color) -> Self {
+        Self {
+            lifetime: 0.5,
+            velocity: Vec2::ZERO,
+            spread: Vec2::splat(180.),
+            gravity: Vec2::new(0., -300.),
+            size: 5.,
+            radius: 8.,
+            start_color: color,
+            end_color: color.with_a(0.),
+        }
+    }
+
+    pub fn aura(color: Color) -> Self {
+        Self {
+            lifetime: 0.8,
+            velocity: Vec2::new(0., 40.),
+            spread: Vec2::new(15., 10.),
+            gravity: Vec2::ZERO,
+            size: 4.,
+            radius: 24.,
+            start_color: color.with_a(0.7),
+            end_color: color.with_a(0.),
+        }
+    }
+}
+
+pub enum EmitterMode {
+    /// Emits this many particles at once, then despawns the emitter.
+    Burst(usize),
+    /// Emits this many particles per second until removed.
+    Continuous(f32),
+}
+
+/// Spawns sprite particles around its entity, in world space so that they are left behind as
+/// the entity moves.
+#[derive(Component)]
+pub struct ParticleEmitter {
+    pub mode: EmitterMode,
+    pub settings: ParticleSettings,
+    /// Fraction of a particle carried over between frames in continuous mode.
+    accumulator: f32,
+}
+
+impl ParticleEmitter {
+    pub fn burst(count: usize, settings: ParticleSettings) -> Self {
+        Self {
+            mode: EmitterMode::Burst(count),
+            settings,
+            accumulator: 0.,
+        }
+    }
+
+    pub fn continuous(rate: f32, settings: ParticleSettings) -> Self {
+        Self {
+            mode: EmitterMode::Continuous(rate),
+            settings,
+            accumulator: 0.,
+        }
+    }
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    gravity: Vec2,
+    timer: Timer,
+    start_color: Color,
+    end_color: Color,
+}
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(GameState::Level), despawn_particles)
+            .add_systems(Update, particle_system)
+            .add_systems(
+                PostUpdate,
+                // Emitters attached to moving entities need their final position for this frame
+                particle_emitter_system.after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Spawns a burst of particles at a position in the world.
+pub fn spawn_burst(
+    commands: &mut Commands,
+    position: Vec2,
+    count: usize,
+    settings: ParticleSettings,
+) {
+    commands.spawn((
+        TransformBundle::from_transform(Transform::from_translation(position.extend(0.))),
+        ParticleEmitter::burst(count, settings),
+    ));
+}
+
+#[allow(clippy::type_complexity)]
+fn despawn_particles(
+    mut commands: Commands,
+    query: Query<Entity, (Or<(With<Particle>, With<ParticleEmitter>)>, Without<Parent>)>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn particle_emitter_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut ParticleEmitter, &GlobalTransform)>,
+) {
+    // Purely cosmetic, so it does not need to come from the level seed
+    let mut rng = rand::thread_rng();
+
+    for (entity, mut emitter, global_tf) in query.iter_mut() {
+        let count = match emitter.mode {
+            EmitterMode::Burst(count) => {
+                commands.entity(entity).despawn_recursive();
+                count
+            }
+            EmitterMode::Continuous(rate) => {
+                emitter.accumulator += rate * time.delta_seconds();
+                let count = emitter.accumulator.floor();
+                emitter.accumulator -= count;
+                count as usize
+            }
+        };
+
+        let settings = &emitter.settings;
+        let origin = global_tf.translation().truncate();
+
+        for _ in 0..count {
+            let offset = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU))
+                * rng.gen_range(0.0..=settings.radius);
+            let velocity = settings.velocity
+                + Vec2::new(
+                    rng.gen_range(-1.0..=1.) * settings.spread.x,
+                    rng.gen_range(-1.0..=1.) * settings.spread.y,
+                );
+
+            // Set the global transform as well, as these spawn after transforms are propagated
+            let transform = Transform::from_translation((origin + offset).extend(0.5));
+
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: settings.start_color,
+                        custom_size: Some(Vec2::splat(settings.size)),
+                        ..Default::default()
+                    },
+                    transform,
+                    global_transform: transform.into(),
+                    ..Default::default()
+                },
+                Particle {
+                    velocity,
+                    gravity: settings.gravity,
+                    timer: Timer::from_seconds(settings.lifetime, TimerMode::Once),
+                    start_color: settings.start_color,
+                    end_color: settings.end_color,
+                },
+            ));
+        }
+    }
+}
+
+fn particle_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let delta = time.delta_seconds();
+
+    for (entity, mut particle, mut tf, mut sprite) in query.iter_mut() {
+        if particle.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let gravity = particle.gravity;
+        particle.velocity += gravity * delta;
+        tf.translation += (particle.velocity * delta).extend(0.);
+
+        let start = Vec4::from(particle.start_color.as_rgba_f32());
+        let end = Vec4::from(particle.end_color.as_rgba_f32());
+        sprite.color = Color::from(start.lerp(end, particle.timer.percent()));
+    }
+}
@@ -1,19 +1,76 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, text::TextLayoutInfo};
 
-use super::SPRITE_SCALE;
-use crate::{components::RectCollisionShape, GameAssets, GameState};
+use super::{
+    animation::SpriteAnimation,
+    particles::{spawn_burst, ParticleSettings},
+    squash::SquashStretch,
+    tuning::GameTuning,
+    LevelEnding, Stopwatch,
+};
+use crate::{components::RectCollisionShape, GameAssets, GameState, UIAssets};
 
-const ANIMATION_SPEED: f32 = 3.;
+/// Fraction of the time limit left when the patient starts to look unwell.
+const WORSENING_FRACTION: f32 = 0.5;
+const CRITICAL_FRACTION: f32 = 0.2;
+const CURE_STRETCH: f32 = 0.3;
+const CURE_PARTICLES: usize = 24;
+/// Height of the speech bubble above the patient's feet, in sprite pixels.
+const SPEECH_BUBBLE_HEIGHT: f32 = 26.;
+const SPEECH_BUBBLE_PADDING: Vec2 = Vec2::new(16., 8.);
 
 #[derive(Event)]
 pub struct SpawnPatientEvent(pub Vec2);
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PatientState {
+    Healthy,
+    Worsening,
+    Critical,
+    Cured,
+    Expired,
+}
+
+impl PatientState {
+    fn animation(&self) -> &'static str {
+        match self {
+            PatientState::Healthy => "healthy",
+            PatientState::Worsening => "worsening",
+            PatientState::Critical => "critical",
+            PatientState::Cured => "cured",
+            PatientState::Expired => "expired",
+        }
+    }
+
+    fn tint(&self) -> Color {
+        match self {
+            PatientState::Healthy | PatientState::Cured => Color::WHITE,
+            PatientState::Worsening => Color::rgb(0.9, 1., 0.75),
+            PatientState::Critical => Color::rgb(1., 0.7, 0.7),
+            PatientState::Expired => Color::GRAY,
+        }
+    }
+
+    fn speech(&self) -> Option<&'static str> {
+        match self {
+            PatientState::Healthy | PatientState::Expired => None,
+            PatientState::Worsening => Some("I don't feel so good..."),
+            PatientState::Critical => Some("Hurry!"),
+            PatientState::Cured => Some("Thank you!"),
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Patient {
-    animation_timer: Timer,
-    animation_length: usize,
+    state: PatientState,
 }
 
+#[derive(Component)]
+struct SpeechBubble;
+
+#[derive(Component)]
+struct SpeechBubbleText;
+
 pub struct PatientPlugin;
 
 impl Plugin for PatientPlugin {
@@ -23,8 +80,10 @@ impl Plugin for PatientPlugin {
                 Update,
                 (
                     spawn_patient.run_if(on_event::<SpawnPatientEvent>()),
-                    patient_animation_system,
+                    patient_state_system,
+                    speech_bubble_size_system,
                 )
+                    .chain()
                     .run_if(in_state(GameState::Level)),
             )
             .add_systems(OnExit(GameState::Level), despawn_patient);
@@ -35,48 +94,138 @@ fn spawn_patient(
     mut events: EventReader<SpawnPatientEvent>,
     mut commands: Commands,
     game_assets: Res<GameAssets>,
+    ui_assets: Res<UIAssets>,
+    tuning: Res<GameTuning>,
 ) {
     for event in events.read() {
-        commands.spawn((
-            SpriteSheetBundle {
-                texture_atlas: game_assets.patient.clone(),
-                transform: Transform {
-                    translation: event.0.extend(0.),
-                    scale: Vec3::ONE * SPRITE_SCALE,
+        let state = PatientState::Healthy;
+
+        commands
+            .spawn((
+                SpriteSheetBundle {
+                    transform: Transform {
+                        translation: event.0.extend(0.),
+                        scale: Vec3::ONE * tuning.sprite_scale,
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
-                ..Default::default()
-            },
-            Patient {
-                animation_timer: Timer::new(
-                    std::time::Duration::from_secs_f32(1. / ANIMATION_SPEED),
-                    TimerMode::Repeating,
-                ),
-                animation_length: 4,
-            },
-            RectCollisionShape {
-                size: Vec2::new(14., 32.) * SPRITE_SCALE,
-                collide: false,
-            },
-        ));
+                SpriteAnimation::new(game_assets.patient_animations.clone(), state.animation()),
+                SquashStretch::default(),
+                Patient { state },
+                RectCollisionShape {
+                    size: Vec2::new(14., 32.) * tuning.sprite_scale,
+                    collide: false,
+                },
+            ))
+            .with_children(|n| {
+                // Undoes the sprite scale so that the text is drawn at its normal size
+                n.spawn((
+                    SpriteBundle {
+                        transform: Transform {
+                            translation: Vec3::new(0., SPEECH_BUBBLE_HEIGHT, 1.),
+                            scale: Vec3::ONE / tuning.sprite_scale,
+                            ..Default::default()
+                        },
+                        visibility: Visibility::Hidden,
+                        ..Default::default()
+                    },
+                    SpeechBubble,
+                ))
+                .with_children(|n| {
+                    n.spawn((
+                        Text2dBundle {
+                            text: Text::from_section(
+                                "",
+                                TextStyle {
+                                    font: ui_assets.font.clone(),
+                                    font_size: 24.,
+                                    color: Color::BLACK,
+                                },
+                            ),
+                            transform: Transform::from_xyz(0., 0., 0.1),
+                            ..Default::default()
+                        },
+                        SpeechBubbleText,
+                    ));
+                });
+            });
     }
 }
 
 fn despawn_patient(mut commands: Commands, query: Query<Entity, With<Patient>>) {
     if let Ok(entity) = query.get_single() {
-        commands.entity(entity).despawn();
+        commands.entity(entity).despawn_recursive();
     }
 }
 
-fn patient_animation_system(
-    time: Res<Time>,
-    mut query: Query<(&mut TextureAtlasSprite, &mut Patient)>,
+fn patient_state_system(
+    mut commands: Commands,
+    stopwatch: Res<Stopwatch>,
+    ending: Option<Res<LevelEnding>>,
+    mut patient_query: Query<(
+        &mut Patient,
+        &mut SpriteAnimation,
+        &mut TextureAtlasSprite,
+        &mut SquashStretch,
+        &Transform,
+        &Children,
+    )>,
+    mut bubble_query: Query<(&mut Visibility, &Children), With<SpeechBubble>>,
+    mut text_query: Query<&mut Text, With<SpeechBubbleText>>,
 ) {
-    if let Ok((mut sprite, mut patient)) = query.get_single_mut() {
-        patient.animation_timer.tick(time.delta());
+    let state = match ending.map(|ending| ending.next_state.clone()) {
+        Some(GameState::LevelCompleted) => PatientState::Cured,
+        Some(_) => PatientState::Expired,
+        None if stopwatch.remaining_fraction() > WORSENING_FRACTION => PatientState::Healthy,
+        None if stopwatch.remaining_fraction() > CRITICAL_FRACTION => PatientState::Worsening,
+        None => PatientState::Critical,
+    };
 
-        if patient.animation_timer.finished() {
-            sprite.index = (sprite.index + 1) % patient.animation_length;
+    for (mut patient, mut animation, mut sprite, mut squash, tf, children) in
+        patient_query.iter_mut()
+    {
+        if patient.state == state {
+            continue;
+        }
+
+        patient.state = state;
+        animation.play(state.animation());
+        sprite.color = state.tint();
+
+        if state == PatientState::Cured {
+            squash.stretch(CURE_STRETCH);
+            spawn_burst(
+                &mut commands,
+                tf.translation.truncate(),
+                CURE_PARTICLES,
+                ParticleSettings::sparkle(Color::LIME_GREEN),
+            );
+        }
+
+        let mut bubbles = bubble_query.iter_many_mut(children);
+        while let Some((mut visibility, bubble_children)) = bubbles.fetch_next() {
+            *visibility = if state.speech().is_some() {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+
+            let mut texts = text_query.iter_many_mut(bubble_children);
+            while let Some(mut text) = texts.fetch_next() {
+                text.sections[0].value = state.speech().unwrap_or_default().to_string();
+            }
+        }
+    }
+}
+
+fn speech_bubble_size_system(
+    text_query: Query<(&TextLayoutInfo, &Parent), Changed<TextLayoutInfo>>,
+    mut bubble_query: Query<&mut Sprite, With<SpeechBubble>>,
+) {
+    for (layout, parent) in text_query.iter() {
+        if let Ok(mut sprite) = bubble_query.get_mut(parent.get()) {
+            sprite.custom_size = Some(layout.logical_size + SPEECH_BUBBLE_PADDING);
         }
     }
 }
@@ -1,21 +1,19 @@
 use bevy::prelude::*;
 
-use super::{side_effects::SideEffect, SPRITE_SCALE};
+use super::{animation::SpriteAnimation, side_effects::SideEffect, tuning::GameTuning};
 use crate::{components::RectCollisionShape, GameAssets, GameState};
 
-const ANIMATION_SPEED: f32 = 44.; // frames per second
-
 #[derive(Event)]
 pub struct SpawnPillEvent {
     pub position: Vec2,
     pub side_effect: SideEffect,
+    pub bonus: bool,
 }
 
 #[derive(Component, Reflect)]
 pub struct Pill {
-    animation_timer: Timer,
-    animation_length: usize,
     pub side_effect: SideEffect,
+    pub bonus: bool,
 }
 
 pub struct PillPlugin;
@@ -25,10 +23,8 @@ impl Plugin for PillPlugin {
         app.add_event::<SpawnPillEvent>()
             .add_systems(
                 Update,
-                (
-                    spawn_pill.run_if(on_event::<SpawnPillEvent>()),
-                    pill_animation_system,
-                )
+                spawn_pill
+                    .run_if(on_event::<SpawnPillEvent>())
                     .run_if(in_state(GameState::Level)),
             )
             .add_systems(OnExit(GameState::Level), despawn_pills);
@@ -42,25 +38,30 @@ fn spawn_pill(
     mut events: EventReader<SpawnPillEvent>,
     mut commands: Commands,
     game_assets: Res<GameAssets>,
+    tuning: Res<GameTuning>,
 ) {
     for event in events.read() {
         commands.spawn((
             SpriteSheetBundle {
-                texture_atlas: game_assets.pill.clone(),
+                sprite: TextureAtlasSprite {
+                    color: if event.bonus {
+                        Color::GOLD
+                    } else {
+                        Color::WHITE
+                    },
+                    ..Default::default()
+                },
                 transform: Transform {
                     translation: event.position.extend(0.),
-                    scale: Vec3::ONE * SPRITE_SCALE,
+                    scale: Vec3::ONE * tuning.sprite_scale,
                     ..Default::default()
                 },
                 ..Default::default()
             },
+            SpriteAnimation::new(game_assets.pill_animations.clone(), "spin"),
             Pill {
-                animation_timer: Timer::new(
-                    std::time::Duration::from_secs_f32(1. / ANIMATION_SPEED),
-                    TimerMode::Repeating,
-                ),
-                animation_length: 45,
-                side_effect: event.side_effect.clone(),
+                side_effect: event.side_effect,
+                bonus: event.bonus,
             },
             RectCollisionShape {
                 size: Vec2::new(18., 22.),
@@ -75,13 +76,3 @@ fn despawn_pills(mut commands: Commands, query: Query<Entity, With<Pill>>) {
         commands.entity(entity).despawn();
     }
 }
-
-fn pill_animation_system(time: Res<Time>, mut query: Query<(&mut TextureAtlasSprite, &mut Pill)>) {
-    for (mut sprite, mut pill) in query.iter_mut() {
-        pill.animation_timer.tick(time.delta());
-
-        if pill.animation_timer.finished() {
-            sprite.index = (sprite.index + 1) % pill.animation_length;
-        }
-    }
-}
@@ -1,10 +1,21 @@
 use bevy::prelude::*;
 
-use super::SPRITE_SCALE;
-use crate::{components::RectCollisionShape, GameAssets, GameState};
+use super::tuning::GameTuning;
+use crate::{
+    components::{RectCollisionShape, Surface},
+    GameAssets, GameState,
+};
+
+/// Size of a platform sprite before `sprite_scale` is applied.
+pub const PLATFORM_SIZE: Vec2 = Vec2::new(64., 16.);
 
 #[derive(Event)]
-pub struct SpawnPlatformEvent(pub Vec2);
+pub struct SpawnPlatformEvent {
+    pub position: Vec2,
+    /// Size in world units. The platform texture is repeated to fill it.
+    pub size: Vec2,
+    pub surface: Surface,
+}
 
 #[derive(Component)]
 struct Platform;
@@ -27,21 +38,36 @@ fn spawn_platform(
     mut events: EventReader<SpawnPlatformEvent>,
     mut commands: Commands,
     game_assets: Res<GameAssets>,
+    tuning: Res<GameTuning>,
 ) {
     for event in events.read() {
+        let tint = match event.surface {
+            Surface::Normal => Color::WHITE,
+            Surface::Ice => Color::rgb(0.7, 0.9, 1.),
+            Surface::Sticky => Color::rgb(0.75, 1., 0.6),
+        };
+        let texture_size = event.size / tuning.sprite_scale;
+
         commands.spawn((
             SpriteBundle {
                 texture: game_assets.platform.clone(),
+                sprite: Sprite {
+                    color: tint,
+                    custom_size: Some(texture_size),
+                    rect: Some(Rect::from_corners(Vec2::ZERO, texture_size)),
+                    ..Default::default()
+                },
                 transform: Transform {
-                    translation: event.0.extend(0.),
-                    scale: Vec3::ONE * SPRITE_SCALE,
+                    translation: event.position.extend(0.),
+                    scale: Vec3::ONE * tuning.sprite_scale,
                     ..Default::default()
                 },
                 ..Default::default()
             },
             Platform,
+            event.surface,
             RectCollisionShape {
-                size: Vec2::new(64., 16.) * SPRITE_SCALE,
+                size: event.size,
                 collide: true,
             },
         ));
@@ -1,38 +1,81 @@
 use bevy::{prelude::*, sprite::collide_aabb::collide};
 use bevy_kira_audio::prelude::*;
+use bincode::{Decode, Encode};
+use serde::Deserialize;
 
 use super::{
+    animation::{AnimationFrameEvent, SpriteAnimation},
+    camera::CameraEffectEvent,
+    clock::Clock,
+    particles::{spawn_burst, ParticleSettings},
     patient::Patient,
     pill::{Pill, SpawnPillEvent},
     platform::SpawnPlatformEvent,
-    side_effects::ApplySideEffectEvent,
-    CollectedLabel, LevelData, Levels, SPRITE_SCALE,
+    replay::{player_input_system, PlayerInput, ReplayPlayback},
+    side_effects::{apply_side_effect, ApplySideEffectEvent},
+    squash::SquashStretch,
+    tuning::GameTuning,
+    AdjustTimeEvent, BonusLabel, CollectedLabel, LevelData, LevelEnding, LevelResult, Levels,
+    Stopwatch,
 };
 use crate::{
-    components::{Gravity, RectCollisionShape, Velocity},
-    AudioAssets, GameAssets, GameData, GameState, MainCamera, SFXChannel,
+    components::{Gravity, Grounded, RectCollisionShape, Velocity, WallContact},
+    AudioAssets, GameAssets, GameData, GameState, SFXChannel,
 };
 
-const ANIMATION_SPEED: f32 = 16.; // frames per second
-const RUN_SPEED: f32 = 350.;
-const JUMP_SPEED: f32 = 1000.;
+const PILL_BURST_PARTICLES: usize = 16;
+const FOOTSTEP_DUST_PARTICLES: usize = 3;
+const LANDING_DUST_PARTICLES: usize = 10;
+
+#[derive(Event)]
+pub struct SpawnPlayerEvent {
+    pub abilities: Abilities,
+}
 
 #[derive(Component, Reflect, Default)]
 pub struct Player {
-    pub animation_timer: Timer,
-    pub animation_length: usize,
     pub medicines_collected: u32,
+    pub bonus_collected: u32,
     pub jump_multiplier: f32,
     pub speed_multiplier: f32,
+    pub abilities: Abilities,
+    pub wall_jumping: bool,
+    /// Downward speed on the last tick spent in the air.
+    pub fall_speed: f32,
+}
+
+/// Movement abilities that a level unlocks for the player.
+#[derive(Reflect, Deserialize, Default, Clone, Copy)]
+pub struct Abilities {
+    #[serde(default)]
+    pub wall_slide: bool,
+    #[serde(default)]
+    pub wall_jump: bool,
 }
 
-#[derive(States, Default, Clone, Debug, Hash, Eq, PartialEq)]
-enum PlayerState {
+#[derive(States, Encode, Decode, Default, Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum PlayerState {
     #[default]
     Idle,
     Running,
     Jumping,
     Falling,
+    WallSliding,
+    WallJumping,
+}
+
+impl PlayerState {
+    /// Name of the animation in the player's animation library.
+    pub fn animation(&self) -> &'static str {
+        match self {
+            PlayerState::Idle => "idle",
+            PlayerState::Running => "run",
+            PlayerState::Jumping => "jump",
+            PlayerState::Falling => "fall",
+            PlayerState::WallSliding => "wall_slide",
+            PlayerState::WallJumping => "wall_jump",
+        }
+    }
 }
 
 pub struct PlayerPlugin;
@@ -40,28 +83,38 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_state::<PlayerState>()
-            .add_systems(OnEnter(GameState::Level), spawn_player)
+            .add_event::<SpawnPlayerEvent>()
             .add_systems(OnExit(GameState::Level), despawn_player)
             .add_systems(
                 Update,
                 (
+                    spawn_player.run_if(on_event::<SpawnPlayerEvent>()),
                     player_state_system,
-                    player_atlas_change_system
+                    player_animation_system
                         .run_if(state_changed::<PlayerState>())
                         .after(player_state_system),
-                    player_animation_system.after(player_atlas_change_system),
-                    camera_follow_system,
-                    player_jump_system,
-                    player_out_of_bounds_system,
+                    player_out_of_bounds_system.run_if(not(resource_exists::<LevelEnding>())),
+                    player_footstep_system.run_if(on_event::<AnimationFrameEvent>()),
                 )
                     .run_if(in_state(GameState::Level)),
             )
             .add_systems(
                 FixedUpdate,
                 (
-                    player_movement_system,
-                    player_pill_collision_system,
-                    player_patient_collision_system,
+                    (player_movement_system, player_jump_system)
+                        .after(player_input_system)
+                        .before(super::gravity_system),
+                    player_wall_slide_system
+                        .after(super::gravity_system)
+                        .before(super::velocity_system),
+                    player_pill_collision_system
+                        .after(super::collision_system)
+                        .before(apply_side_effect),
+                    player_clock_collision_system
+                        .after(super::collision_system)
+                        .before(super::adjust_time_system),
+                    player_landing_system.after(super::collision_system),
+                    player_patient_collision_system.after(super::stopwatch_system),
                 )
                     .run_if(in_state(GameState::Level)),
             );
@@ -74,38 +127,47 @@ impl Plugin for PlayerPlugin {
     }
 }
 
-fn spawn_player(mut commands: Commands, game_assets: Res<GameAssets>) {
-    commands.spawn((
-        SpriteSheetBundle {
-            texture_atlas: game_assets.player_idle.clone(),
-            transform: Transform {
-                scale: Vec3::ONE * SPRITE_SCALE,
+fn spawn_player(
+    mut events: EventReader<SpawnPlayerEvent>,
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    tuning: Res<GameTuning>,
+) {
+    for event in events.read() {
+        commands.spawn((
+            SpriteSheetBundle {
+                transform: Transform {
+                    scale: Vec3::ONE * tuning.sprite_scale,
+                    ..Default::default()
+                },
                 ..Default::default()
             },
-            ..Default::default()
-        },
-        Player {
-            animation_timer: Timer::new(
-                std::time::Duration::from_secs_f32(1. / ANIMATION_SPEED),
-                TimerMode::Repeating,
-            ),
-            animation_length: 15,
-            medicines_collected: 0,
-            jump_multiplier: 1.,
-            speed_multiplier: 1.,
-        },
-        Velocity(Vec2::ZERO),
-        Gravity(Vec2::NEG_Y),
-        RectCollisionShape {
-            size: Vec2::new(14., 32.) * SPRITE_SCALE,
-            collide: true,
-        },
-    ));
+            SpriteAnimation::new(game_assets.player_animations.clone(), "idle"),
+            SquashStretch::default(),
+            Player {
+                medicines_collected: 0,
+                bonus_collected: 0,
+                jump_multiplier: 1.,
+                speed_multiplier: 1.,
+                abilities: event.abilities,
+                wall_jumping: false,
+                fall_speed: 0.,
+            },
+            Velocity(Vec2::ZERO),
+            Gravity(Vec2::NEG_Y),
+            Grounded::default(),
+            WallContact::default(),
+            RectCollisionShape {
+                size: Vec2::new(14., 32.) * tuning.sprite_scale,
+                collide: true,
+            },
+        ));
+    }
 }
 
 fn despawn_player(mut commands: Commands, query: Query<Entity, With<Player>>) {
     if let Ok(entity) = query.get_single() {
-        commands.entity(entity).despawn();
+        commands.entity(entity).despawn_recursive();
     }
 }
 
@@ -113,6 +175,7 @@ fn despawn_player(mut commands: Commands, query: Query<Entity, With<Player>>) {
 #[cfg(debug_assertions)]
 fn spawn_game_entity(
     kb: Res<Input<KeyCode>>,
+    tuning: Res<GameTuning>,
     query: Query<&Transform, With<Player>>,
     mut platform_events: EventWriter<SpawnPlatformEvent>,
     mut pill_events: EventWriter<SpawnPillEvent>,
@@ -121,13 +184,18 @@ fn spawn_game_entity(
         let player_pos = player_tf.translation.truncate();
 
         if kb.just_pressed(KeyCode::P) {
-            platform_events.send(SpawnPlatformEvent(player_pos - Vec2::new(0., 90.)));
+            platform_events.send(SpawnPlatformEvent {
+                position: player_pos - Vec2::new(0., 90.),
+                size: super::platform::PLATFORM_SIZE * tuning.sprite_scale,
+                surface: Default::default(),
+            });
         }
 
         if kb.just_pressed(KeyCode::O) {
             pill_events.send(SpawnPillEvent {
                 position: player_pos + Vec2::new(50., 0.),
                 side_effect: rand::random(),
+                bonus: false,
             });
         }
     }
@@ -135,13 +203,25 @@ fn spawn_game_entity(
 
 fn player_state_system(
     mut player_state: ResMut<NextState<PlayerState>>,
-    query: Query<&Velocity, (With<Player>, Changed<Velocity>)>,
+    mut query: Query<(&Velocity, &WallContact, &mut Player), Changed<Velocity>>,
 ) {
-    if let Ok(velocity) = query.get_single() {
+    if let Ok((velocity, wall_contact, mut player)) = query.get_single_mut() {
+        if velocity.0.y <= 0. {
+            player.wall_jumping = false;
+        }
+
         let next_state = if velocity.0.y > 0. {
-            PlayerState::Jumping
+            if player.wall_jumping {
+                PlayerState::WallJumping
+            } else {
+                PlayerState::Jumping
+            }
         } else if velocity.0.y < 0. {
-            PlayerState::Falling
+            if player.abilities.wall_slide && wall_contact.0.is_some() {
+                PlayerState::WallSliding
+            } else {
+                PlayerState::Falling
+            }
         } else if velocity.0.x != 0. {
             PlayerState::Running
         } else {
@@ -152,64 +232,30 @@ fn player_state_system(
     }
 }
 
-fn player_atlas_change_system(
-    player_state: Res<State<PlayerState>>,
-    game_assets: Res<GameAssets>,
-    texture_atlases: Res<Assets<TextureAtlas>>,
-    mut query: Query<(
-        &mut Handle<TextureAtlas>,
-        &mut TextureAtlasSprite,
-        &mut Player,
-    )>,
-) {
-    if let Ok((mut atlas, mut sprite, mut player)) = query.get_single_mut() {
-        let new_atlas = match player_state.get() {
-            PlayerState::Idle => game_assets.player_idle.clone(),
-            PlayerState::Running => game_assets.player_run.clone(),
-            PlayerState::Jumping => game_assets.player_jump.clone(),
-            PlayerState::Falling => game_assets.player_fall.clone(),
-        };
-
-        if *atlas != new_atlas {
-            player.animation_length = texture_atlases.get(&new_atlas).unwrap().textures.len();
-            *atlas = new_atlas;
-            sprite.index = 0;
-        }
-    }
-}
-
 fn player_animation_system(
-    time: Res<Time>,
-    mut query: Query<(&mut TextureAtlasSprite, &mut Player)>,
-) {
-    if let Ok((mut sprite, mut player)) = query.get_single_mut() {
-        player.animation_timer.tick(time.delta());
-
-        if player.animation_timer.finished() {
-            sprite.index = (sprite.index + 1) % player.animation_length;
-        }
-    }
-}
-
-fn camera_follow_system(
-    mut camera_query: Query<&mut Transform, With<MainCamera>>,
-    player_query: Query<&Transform, (With<Player>, Without<MainCamera>)>,
+    player_state: Res<State<PlayerState>>,
+    mut query: Query<&mut SpriteAnimation, With<Player>>,
 ) {
-    if let Ok(mut camera_tf) = camera_query.get_single_mut() {
-        if let Ok(player_tf) = player_query.get_single() {
-            camera_tf.translation = player_tf.translation;
-        }
+    if let Ok(mut animation) = query.get_single_mut() {
+        animation.play(player_state.get().animation());
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn player_pill_collision_system(
     mut commands: Commands,
     sfx: Res<AudioChannel<SFXChannel>>,
     audio_assets: Res<AudioAssets>,
     mut side_effect_events: EventWriter<ApplySideEffectEvent>,
+    mut camera_events: EventWriter<CameraEffectEvent>,
+    game_data: Res<GameData>,
+    level_assets: Res<Assets<LevelData>>,
+    levels: Res<Levels>,
     mut player_query: Query<(&Transform, &RectCollisionShape, &mut Player)>,
     pill_query: Query<(Entity, &Transform, &RectCollisionShape, &Pill), Without<Player>>,
+    patient_query: Query<&Transform, (With<Patient>, Without<Player>)>,
     mut label_query: Query<&mut Text, With<CollectedLabel>>,
+    mut bonus_label_query: Query<&mut Text, (With<BonusLabel>, Without<CollectedLabel>)>,
 ) {
     if let Ok((player_tf, player_col, mut player)) = player_query.get_single_mut() {
         for (pill_entity, pill_tf, pill_col, pill) in pill_query.iter() {
@@ -222,11 +268,45 @@ fn player_pill_collision_system(
 
             if collision.is_some() {
                 sfx.play(audio_assets.pill_collect.clone());
-                player.medicines_collected += 1;
                 commands.entity(pill_entity).despawn();
 
-                if let Ok(mut text) = label_query.get_single_mut() {
-                    text.sections[1].value = player.medicines_collected.to_string();
+                let sparkle_color = if pill.bonus {
+                    Color::GOLD
+                } else {
+                    Color::WHITE
+                };
+                spawn_burst(
+                    &mut commands,
+                    pill_tf.translation.truncate(),
+                    PILL_BURST_PARTICLES,
+                    ParticleSettings::sparkle(sparkle_color),
+                );
+
+                if pill.bonus {
+                    player.bonus_collected += 1;
+
+                    if let Ok(mut text) = bonus_label_query.get_single_mut() {
+                        text.sections[1].value = player.bonus_collected.to_string();
+                    }
+                } else {
+                    player.medicines_collected += 1;
+
+                    if let Ok(mut text) = label_query.get_single_mut() {
+                        text.sections[1].value = player.medicines_collected.to_string();
+                    }
+
+                    let level_handle = levels.0.get(&game_data.slot().current_level).unwrap();
+                    let pill_goal = level_assets.get(level_handle).unwrap().pill_goal;
+
+                    // Shows where to take the pills once there are enough of them
+                    if player.medicines_collected == pill_goal {
+                        if let Ok(patient_tf) = patient_query.get_single() {
+                            camera_events.send(CameraEffectEvent::Focus(
+                                patient_tf.translation.truncate(),
+                                1.5,
+                            ));
+                        }
+                    }
                 }
 
                 side_effect_events.send(ApplySideEffectEvent(pill.side_effect));
@@ -235,14 +315,97 @@ fn player_pill_collision_system(
     }
 }
 
+fn player_clock_collision_system(
+    mut commands: Commands,
+    mut time_events: EventWriter<AdjustTimeEvent>,
+    mut camera_events: EventWriter<CameraEffectEvent>,
+    player_query: Query<(&Transform, &RectCollisionShape), With<Player>>,
+    clock_query: Query<(Entity, &Transform, &RectCollisionShape, &Clock), Without<Player>>,
+) {
+    if let Ok((player_tf, player_col)) = player_query.get_single() {
+        for (clock_entity, clock_tf, clock_col, clock) in clock_query.iter() {
+            let collision = collide(
+                player_tf.translation,
+                player_col.size,
+                clock_tf.translation,
+                clock_col.size,
+            );
+
+            if collision.is_some() {
+                commands.entity(clock_entity).despawn_recursive();
+                time_events.send(AdjustTimeEvent(clock.seconds));
+
+                if clock.seconds < 0. {
+                    camera_events.send(CameraEffectEvent::Shake(0.5));
+                }
+            }
+        }
+    }
+}
+
+fn player_landing_system(
+    mut commands: Commands,
+    tuning: Res<GameTuning>,
+    mut camera_events: EventWriter<CameraEffectEvent>,
+    mut query: Query<(
+        &Transform,
+        &RectCollisionShape,
+        &Velocity,
+        &Grounded,
+        &mut SquashStretch,
+        &mut Player,
+    )>,
+) {
+    if let Ok((tf, collision_shape, velocity, grounded, mut squash, mut player)) =
+        query.get_single_mut()
+    {
+        if grounded.0.is_none() {
+            player.fall_speed = (-velocity.0.y).max(0.);
+            return;
+        }
+
+        let hard_landing_speed = tuning.player.hard_landing_speed;
+
+        if player.fall_speed > hard_landing_speed {
+            let trauma = (player.fall_speed / hard_landing_speed - 1.).clamp(0.2, 0.6);
+            camera_events.send(CameraEffectEvent::Shake(trauma));
+        }
+
+        if player.fall_speed > tuning.squash.min_landing_speed {
+            let impact = (player.fall_speed / hard_landing_speed).min(1.);
+            squash.squash(tuning.squash.landing_squash * impact);
+        }
+
+        if player.fall_speed > tuning.player.landing_dust_speed {
+            spawn_burst(
+                &mut commands,
+                feet_position(tf, collision_shape),
+                LANDING_DUST_PARTICLES,
+                ParticleSettings::dust(),
+            );
+        }
+
+        player.fall_speed = 0.;
+    }
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn player_patient_collision_system(
-    mut game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
     level_assets: Res<Assets<LevelData>>,
     levels: Res<Levels>,
     game_data: Res<GameData>,
+    playback: Option<Res<ReplayPlayback>>,
     player_query: Query<(&Transform, &RectCollisionShape, &Player)>,
     patient_query: Query<(&Transform, &RectCollisionShape), (With<Patient>, Without<Player>)>,
+    stopwatch: Res<Stopwatch>,
+    ending: Option<Res<LevelEnding>>,
 ) {
+    // Nothing more can change once the level has been won or lost
+    if ending.is_some() {
+        return;
+    }
+
     if let Ok((player_tf, player_col, player)) = player_query.get_single() {
         if let Ok((patient_tf, patient_col)) = patient_query.get_single() {
             let collision = collide(
@@ -253,11 +416,35 @@ fn player_patient_collision_system(
             );
 
             if collision.is_some() {
-                let level_handle = levels.0.get(&game_data.current_level).unwrap();
+                let level_handle = levels.0.get(&game_data.slot().current_level).unwrap();
                 let level_data = level_assets.get(level_handle).unwrap();
 
-                if player.medicines_collected == level_data.pill_goal {
-                    game_state.set(GameState::LevelCompleted);
+                if player.medicines_collected >= level_data.pill_goal {
+                    let time = stopwatch.elapsed();
+                    let score = level_data.score(
+                        stopwatch.remaining(),
+                        player.medicines_collected,
+                        player.bonus_collected,
+                    );
+                    let stars = level_data.stars(score);
+
+                    // Recorded once the results screen is reached, which always follows as the
+                    // level cannot be left while the patient reacts
+                    commands.insert_resource(LevelResult {
+                        level: game_data.slot().current_level,
+                        time,
+                        pills_collected: player.medicines_collected,
+                        bonus_collected: player.bonus_collected,
+                        bonus_total: level_data.bonus_pills(),
+                        score,
+                        stars,
+                        max_stars: level_data.star_scores.len() as u8,
+                        new_best: false,
+                        leaderboard_rank: None,
+                        replay: playback.is_some(),
+                    });
+
+                    commands.insert_resource(LevelEnding::new(GameState::LevelCompleted));
                 }
             }
         }
@@ -265,11 +452,13 @@ fn player_patient_collision_system(
 }
 
 fn player_movement_system(
-    kb: Res<Input<KeyCode>>,
-    mut query: Query<(&mut Velocity, &mut TextureAtlasSprite, &Player)>,
+    time: Res<Time<Fixed>>,
+    input: Res<PlayerInput>,
+    tuning: Res<GameTuning>,
+    mut query: Query<(&mut Velocity, &mut TextureAtlasSprite, &Grounded, &Player)>,
 ) {
-    if let Ok((mut velocity, mut sprite, player)) = query.get_single_mut() {
-        let x_direction = kb.pressed(KeyCode::D) as i32 - kb.pressed(KeyCode::A) as i32;
+    if let Ok((mut velocity, mut sprite, grounded, player)) = query.get_single_mut() {
+        let x_direction = input.x_direction;
 
         if x_direction < 0 {
             sprite.flip_x = true;
@@ -277,30 +466,117 @@ fn player_movement_system(
             sprite.flip_x = false;
         }
 
-        velocity.0.x = x_direction as f32 * RUN_SPEED * player.speed_multiplier;
+        let (acceleration, deceleration, speed_factor) = match grounded.0 {
+            Some(surface) => (
+                tuning.player.ground_acceleration * surface.friction(),
+                tuning.player.ground_deceleration * surface.friction(),
+                surface.speed_factor(),
+            ),
+            None => (
+                tuning.player.air_acceleration,
+                tuning.player.air_deceleration,
+                1.,
+            ),
+        };
+
+        let target_speed =
+            x_direction as f32 * tuning.player.run_speed * player.speed_multiplier * speed_factor;
+
+        // Accelerate while pushing along the current direction of travel, otherwise slow down
+        let rate = if x_direction != 0 && target_speed * velocity.0.x >= 0. {
+            acceleration
+        } else {
+            deceleration
+        };
+
+        let max_change = rate * time.delta_seconds();
+        velocity.0.x += (target_speed - velocity.0.x).clamp(-max_change, max_change);
+    }
+}
+
+fn player_wall_slide_system(
+    tuning: Res<GameTuning>,
+    mut query: Query<(&mut Velocity, &WallContact, &Player)>,
+) {
+    if let Ok((mut velocity, wall_contact, player)) = query.get_single_mut() {
+        if player.abilities.wall_slide && wall_contact.0.is_some() {
+            velocity.0.y = velocity.0.y.max(-tuning.player.wall_slide_speed);
+        }
     }
 }
 
 fn player_jump_system(
-    kb: Res<Input<KeyCode>>,
-    player_state: Res<State<PlayerState>>,
+    input: Res<PlayerInput>,
     sfx: Res<AudioChannel<SFXChannel>>,
     audio_assets: Res<AudioAssets>,
-    mut query: Query<(&mut Velocity, &Player)>,
+    tuning: Res<GameTuning>,
+    mut query: Query<(
+        &mut Velocity,
+        &Grounded,
+        &WallContact,
+        &mut SquashStretch,
+        &mut Player,
+    )>,
 ) {
-    if kb.just_pressed(KeyCode::W) {
-        if let Ok((mut velocity, player)) = query.get_single_mut() {
-            match player_state.get() {
-                PlayerState::Idle | PlayerState::Running => {
-                    velocity.0.y = JUMP_SPEED * player.jump_multiplier;
+    if input.jump {
+        if let Ok((mut velocity, grounded, wall_contact, mut squash, mut player)) =
+            query.get_single_mut()
+        {
+            match grounded.0 {
+                Some(_) => {
+                    velocity.0.y = tuning.player.jump_speed * player.jump_multiplier;
+                    squash.stretch(tuning.squash.jump_stretch);
                     sfx.play(audio_assets.player_jump.clone());
                 }
+                _ if player.abilities.wall_jump => {
+                    // Kick off in the opposite direction of the wall being touched
+                    if let Some(wall_direction) = wall_contact.0 {
+                        velocity.0 = Vec2::new(
+                            -wall_direction * tuning.player.wall_jump_speed,
+                            tuning.player.jump_speed * player.jump_multiplier,
+                        );
+                        player.wall_jumping = true;
+                        squash.stretch(tuning.squash.jump_stretch);
+                        sfx.play(audio_assets.player_jump.clone());
+                    }
+                }
                 _ => {}
             }
         }
     }
 }
 
+fn player_footstep_system(
+    mut commands: Commands,
+    sfx: Res<AudioChannel<SFXChannel>>,
+    audio_assets: Res<AudioAssets>,
+    mut events: EventReader<AnimationFrameEvent>,
+    query: Query<(&Transform, &RectCollisionShape, &Grounded), With<Player>>,
+) {
+    let Ok((tf, collision_shape, grounded)) = query.get_single() else {
+        return;
+    };
+
+    for event in events.read() {
+        if event.name != "footstep" || !query.contains(event.entity) || grounded.0.is_none() {
+            continue;
+        }
+
+        sfx.play(audio_assets.footstep.clone());
+
+        spawn_burst(
+            &mut commands,
+            feet_position(tf, collision_shape),
+            FOOTSTEP_DUST_PARTICLES,
+            ParticleSettings::dust(),
+        );
+    }
+}
+
+fn feet_position(tf: &Transform, collision_shape: &RectCollisionShape) -> Vec2 {
+    tf.translation.truncate() - Vec2::Y * collision_shape.size.y / 2.
+}
+
 fn player_out_of_bounds_system(
     mut game_state: ResMut<NextState<GameState>>,
     query: Query<&Transform, With<Player>>,
@@ -0,0 +1,431 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use bincode::{Decode, Encode};
+use thiserror::Error;
+
+use super::{
+    player::{Player, PlayerState},
+    LevelData, LevelEnding, LevelResult, LevelSeed, Levels,
+};
+use crate::{
+    pause_menu::PauseState,
+    save::{save_dir, write_atomic},
+    GameData, GameState,
+};
+
+const REPLAY_MAGIC: [u8; 4] = *b"EXPR";
+const REPLAY_VERSION: u32 = 3;
+
+const LEFT: u8 = 1;
+const RIGHT: u8 = 2;
+const JUMP: u8 = 4;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("Could not access replay file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Could not encode replay: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+
+    #[error("Could not decode replay: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+
+    #[error("Not a replay file")]
+    InvalidFile,
+
+    #[error("Replay version {0} is newer than this game supports")]
+    UnsupportedVersion(u32),
+
+    #[error("Replay version {0} was recorded with different pill side effects")]
+    Outdated(u32),
+}
+
+/// Player input for a single fixed timestep tick.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayerInput {
+    pub x_direction: i8,
+    pub jump: bool,
+}
+
+impl PlayerInput {
+    fn to_bits(self) -> u8 {
+        let mut bits = 0;
+
+        if self.x_direction < 0 {
+            bits |= LEFT;
+        }
+        if self.x_direction > 0 {
+            bits |= RIGHT;
+        }
+        if self.jump {
+            bits |= JUMP;
+        }
+
+        bits
+    }
+
+    fn from_bits(bits: u8) -> Self {
+        Self {
+            x_direction: (bits & RIGHT != 0) as i8 - (bits & LEFT != 0) as i8,
+            jump: bits & JUMP != 0,
+        }
+    }
+}
+
+/// How the player looked on a single tick, so that a run can be shown as a ghost.
+#[derive(Encode, Decode, Clone, Copy)]
+pub struct GhostFrame {
+    pub position: [f32; 2],
+    pub scale: f32,
+    pub state: PlayerState,
+    pub sprite_index: u8,
+    pub flip_x: bool,
+}
+
+#[derive(Encode, Decode)]
+struct ReplayHeader {
+    magic: [u8; 4],
+    version: u32,
+}
+
+/// Every tick of a level attempt, along with what is needed to reproduce it exactly.
+#[derive(Encode, Decode)]
+pub struct Replay {
+    pub level: usize,
+    pub level_hash: u64,
+    pub seed: u64,
+    /// Run-length encoded inputs, as pairs of input bits and the number of ticks they last.
+    inputs: Vec<(u8, u32)>,
+    pub frames: Vec<GhostFrame>,
+}
+
+impl Replay {
+    fn new(
+        level: usize,
+        level_hash: u64,
+        seed: u64,
+        inputs: &[PlayerInput],
+        frames: Vec<GhostFrame>,
+    ) -> Self {
+        let mut runs: Vec<(u8, u32)> = Vec::new();
+
+        for input in inputs {
+            let bits = input.to_bits();
+
+            match runs.last_mut() {
+                Some((last, count)) if *last == bits => *count += 1,
+                _ => runs.push((bits, 1)),
+            }
+        }
+
+        Self {
+            level,
+            level_hash,
+            seed,
+            inputs: runs,
+            frames,
+        }
+    }
+
+    pub fn inputs(&self) -> Vec<PlayerInput> {
+        self.inputs
+            .iter()
+            .flat_map(|(bits, count)| {
+                std::iter::repeat_n(PlayerInput::from_bits(*bits), *count as usize)
+            })
+            .collect()
+    }
+
+    /// The most recent completion of a level.
+    pub fn path(level: usize) -> PathBuf {
+        save_dir()
+            .join("replays")
+            .join(format!("level{}.replay", level))
+    }
+
+    /// The fastest completion of a level.
+    pub fn best_path(level: usize) -> PathBuf {
+        save_dir()
+            .join("replays")
+            .join(format!("level{}.best.replay", level))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, ReplayError> {
+        if !bytes.starts_with(&REPLAY_MAGIC) {
+            return Err(ReplayError::InvalidFile);
+        }
+
+        let config = bincode::config::standard();
+        let (header, read): (ReplayHeader, _) = bincode::decode_from_slice(bytes, config)?;
+        let payload = &bytes[read..];
+
+        match header.version {
+            // Pills were drawn from fewer side effects, so the same seed would play differently
+            1 | 2 => Err(ReplayError::Outdated(header.version)),
+            3 => Ok(bincode::decode_from_slice(payload, config)?.0),
+            version => Err(ReplayError::UnsupportedVersion(version)),
+        }
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, ReplayError> {
+        let config = bincode::config::standard();
+        let header = ReplayHeader {
+            magic: REPLAY_MAGIC,
+            version: REPLAY_VERSION,
+        };
+
+        let mut bytes = bincode::encode_to_vec(header, config)?;
+        bytes.extend(bincode::encode_to_vec(self, config)?);
+        Ok(bytes)
+    }
+
+    fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        write_atomic(path, &self.encode()?, false)?;
+        Ok(())
+    }
+}
+
+/// FNV-1a, which unlike the standard library's hasher is stable across builds.
+pub fn level_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Jumps pressed since the last fixed tick, so that none are lost or doubled when a frame runs
+/// zero or several ticks.
+#[derive(Resource, Default)]
+pub struct JumpBuffer(pub bool);
+
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    level: usize,
+    level_hash: u64,
+    inputs: Vec<PlayerInput>,
+    frames: Vec<GhostFrame>,
+}
+
+/// Present while a replay is being played back instead of reading the keyboard.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub seed: u64,
+    inputs: Vec<PlayerInput>,
+    tick: usize,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: &Replay) -> Self {
+        Self {
+            seed: replay.seed,
+            inputs: replay.inputs(),
+            tick: 0,
+        }
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerInput>()
+            .init_resource::<JumpBuffer>()
+            .init_resource::<ReplayRecorder>()
+            .add_systems(OnEnter(GameState::Level), start_recording)
+            .add_systems(OnExit(GameState::Level), stop_playback)
+            .add_systems(
+                OnEnter(GameState::LevelCompleted),
+                // Playback has already stopped by now, so check the result instead
+                save_replay.run_if(|result: Res<LevelResult>| !result.replay),
+            )
+            .add_systems(
+                Update,
+                jump_buffer_system
+                    .run_if(in_state(GameState::Level).and_then(in_state(PauseState::Unpaused))),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    player_input_system,
+                    record_frame_system
+                        .after(super::collision_system)
+                        .run_if(not(resource_exists::<ReplayPlayback>()))
+                        .run_if(not(resource_exists::<LevelEnding>())),
+                )
+                    .run_if(in_state(GameState::Level)),
+            );
+    }
+}
+
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    mut jump_buffer: ResMut<JumpBuffer>,
+    mut input: ResMut<PlayerInput>,
+    game_data: Res<GameData>,
+    level_assets: Res<Assets<LevelData>>,
+    levels: Res<Levels>,
+) {
+    let level = game_data.slot().current_level;
+    let level_handle = levels.0.get(&level).unwrap();
+
+    *recorder = ReplayRecorder {
+        level,
+        level_hash: level_assets.get(level_handle).unwrap().hash,
+        inputs: Vec::new(),
+        frames: Vec::new(),
+    };
+    jump_buffer.0 = false;
+    *input = PlayerInput::default();
+}
+
+fn stop_playback(mut commands: Commands) {
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+pub fn jump_buffer_system(kb: Res<Input<KeyCode>>, mut jump_buffer: ResMut<JumpBuffer>) {
+    if kb.just_pressed(KeyCode::W) {
+        jump_buffer.0 = true;
+    }
+}
+
+/// Samples the input for this tick, either from the keyboard or from the replay being played
+/// back. Ticks before the player has spawned are skipped so that recordings line up exactly.
+pub fn player_input_system(
+    kb: Res<Input<KeyCode>>,
+    mut jump_buffer: ResMut<JumpBuffer>,
+    mut input: ResMut<PlayerInput>,
+    mut recorder: ResMut<ReplayRecorder>,
+    playback: Option<ResMut<ReplayPlayback>>,
+    ending: Option<Res<LevelEnding>>,
+    player_query: Query<(), With<Player>>,
+) {
+    if player_query.is_empty() {
+        return;
+    }
+
+    // The player stands still while the patient reacts to the end of the level
+    if ending.is_some() {
+        *input = PlayerInput::default();
+        return;
+    }
+
+    let jump = std::mem::take(&mut jump_buffer.0);
+
+    *input = match playback {
+        Some(mut playback) => {
+            let input = playback
+                .inputs
+                .get(playback.tick)
+                .copied()
+                .unwrap_or_default();
+            playback.tick += 1;
+            input
+        }
+        None => {
+            let input = PlayerInput {
+                x_direction: kb.pressed(KeyCode::D) as i8 - kb.pressed(KeyCode::A) as i8,
+                jump,
+            };
+            recorder.inputs.push(input);
+            input
+        }
+    };
+}
+
+fn record_frame_system(
+    mut recorder: ResMut<ReplayRecorder>,
+    player_state: Res<State<PlayerState>>,
+    query: Query<(&Transform, &TextureAtlasSprite), With<Player>>,
+) {
+    if let Ok((tf, sprite)) = query.get_single() {
+        recorder.frames.push(GhostFrame {
+            position: tf.translation.truncate().to_array(),
+            scale: tf.scale.x,
+            state: *player_state.get(),
+            sprite_index: sprite.index as u8,
+            flip_x: sprite.flip_x,
+        });
+    }
+}
+
+fn save_replay(mut recorder: ResMut<ReplayRecorder>, seed: Res<LevelSeed>) {
+    let level = recorder.level;
+    let frames = std::mem::take(&mut recorder.frames);
+    let replay = Replay::new(level, recorder.level_hash, seed.0, &recorder.inputs, frames);
+
+    if let Err(e) = replay.save(&Replay::path(level)) {
+        error!("Could not save replay for level {}: {}", level, e);
+    }
+
+    // Only keep the fastest run for the layout of the level as it is now
+    let is_best = match Replay::load(&Replay::best_path(level)) {
+        Ok(best) => best.level_hash != replay.level_hash || replay.frames.len() < best.frames.len(),
+        Err(_) => true,
+    };
+
+    if is_best {
+        if let Err(e) = replay.save(&Replay::best_path(level)) {
+            error!("Could not save best replay for level {}: {}", level, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_inputs() {
+        let inputs: Vec<PlayerInput> = [(0, false), (1, false), (1, false), (1, true), (-1, false)]
+            .into_iter()
+            .cycle()
+            .take(500)
+            .map(|(x_direction, jump)| PlayerInput { x_direction, jump })
+            .collect();
+
+        let replay = Replay::new(2, level_hash(b"level"), 42, &inputs, Vec::new());
+        let decoded = Replay::decode(&replay.encode().unwrap()).unwrap();
+
+        assert_eq!(decoded.level, 2);
+        assert_eq!(decoded.seed, 42);
+        assert_eq!(decoded.inputs(), inputs);
+    }
+
+    #[test]
+    fn rejects_replays_with_old_side_effects() {
+        let config = bincode::config::standard();
+
+        for version in [1, 2] {
+            let header = ReplayHeader {
+                magic: REPLAY_MAGIC,
+                version,
+            };
+            let inputs = vec![(RIGHT, 30u32), (RIGHT | JUMP, 1)];
+
+            let mut bytes = bincode::encode_to_vec(header, config).unwrap();
+            bytes.extend(bincode::encode_to_vec((1usize, 7u64, 42u64, inputs), config).unwrap());
+
+            assert!(matches!(
+                Replay::decode(&bytes),
+                Err(ReplayError::Outdated(v)) if v == version
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(matches!(
+            Replay::decode(b"not a replay"),
+            Err(ReplayError::InvalidFile)
+        ));
+    }
+}
@@ -1,22 +1,44 @@
 use bevy::prelude::*;
 use rand::distributions::{Distribution, Standard};
 
-use super::player::Player;
+use super::{
+    camera::CameraEffectEvent,
+    particles::{ParticleEmitter, ParticleSettings},
+    player::Player,
+    AdjustTimeEvent,
+};
 use crate::{components::RectCollisionShape, GameState};
 
-#[derive(Clone, Copy, Reflect)]
+/// Seconds taken off the stopwatch by drowsiness.
+const DROWSINESS_TIME_LOSS: f32 = 3.;
+const AURA_PARTICLES_PER_SECOND: f32 = 12.;
+
+#[derive(Clone, Copy, Reflect, PartialEq, Eq)]
 pub enum SideEffect {
     Shrink,
     Speed,
     Slowness,
+    Drowsiness,
 }
 
 impl Distribution<SideEffect> for Standard {
     fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> SideEffect {
-        match rng.gen_range(0..=2) {
+        match rng.gen_range(0..=3) {
             0 => SideEffect::Shrink,
             1 => SideEffect::Speed,
-            _ => SideEffect::Slowness,
+            2 => SideEffect::Slowness,
+            _ => SideEffect::Drowsiness,
+        }
+    }
+}
+
+impl SideEffect {
+    fn aura_color(&self) -> Color {
+        match self {
+            SideEffect::Shrink => Color::PURPLE,
+            SideEffect::Speed => Color::ORANGE,
+            SideEffect::Slowness => Color::MIDNIGHT_BLUE,
+            SideEffect::Drowsiness => Color::SILVER,
         }
     }
 }
@@ -24,24 +46,40 @@ impl Distribution<SideEffect> for Standard {
 #[derive(Event)]
 pub struct ApplySideEffectEvent(pub SideEffect);
 
+/// Particles around the player showing a side effect they have taken.
+#[derive(Component)]
+pub struct SideEffectAura(SideEffect);
+
 pub struct SideEffectsPlugin;
 
 impl Plugin for SideEffectsPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ApplySideEffectEvent>().add_systems(
-            Update,
+            FixedUpdate,
             apply_side_effect
+                .before(super::adjust_time_system)
                 .run_if(in_state(GameState::Level).and_then(on_event::<ApplySideEffectEvent>())),
         );
     }
 }
 
-fn apply_side_effect(
-    mut player_query: Query<(&mut Transform, &mut RectCollisionShape, &mut Player)>,
+pub fn apply_side_effect(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &mut Transform, &mut RectCollisionShape, &mut Player)>,
+    aura_query: Query<&SideEffectAura>,
     mut events: EventReader<ApplySideEffectEvent>,
+    mut time_events: EventWriter<AdjustTimeEvent>,
+    mut camera_events: EventWriter<CameraEffectEvent>,
 ) {
-    if let Ok((mut player_tf, mut player_col, mut player)) = player_query.get_single_mut() {
+    if let Ok((player_entity, mut player_tf, mut player_col, mut player)) =
+        player_query.get_single_mut()
+    {
+        let mut auras: Vec<_> = aura_query.iter().map(|aura| aura.0).collect();
+
         for event in events.read() {
+            camera_events.send(CameraEffectEvent::ZoomPulse(0.15));
+            camera_events.send(CameraEffectEvent::Shake(0.2));
+
             match event.0 {
                 SideEffect::Shrink => {
                     player_tf.scale *= 0.73;
@@ -50,6 +88,24 @@ fn apply_side_effect(
                 }
                 SideEffect::Speed => player.speed_multiplier *= 1.5,
                 SideEffect::Slowness => player.speed_multiplier *= 0.8,
+                SideEffect::Drowsiness => time_events.send(AdjustTimeEvent(-DROWSINESS_TIME_LOSS)),
+            }
+
+            // Effects stack, but each kind only needs one aura
+            if !auras.contains(&event.0) {
+                auras.push(event.0);
+
+                let aura = commands
+                    .spawn((
+                        SpatialBundle::default(),
+                        ParticleEmitter::continuous(
+                            AURA_PARTICLES_PER_SECOND,
+                            ParticleSettings::aura(event.0.aura_color()),
+                        ),
+                        SideEffectAura(event.0),
+                    ))
+                    .id();
+                commands.entity(player_entity).add_child(aura);
             }
         }
     }
@@ -0,0 +1,299 @@
+use bevy::prelude::*;
+
+use super::{
+    player::Player,
+    replay::{player_input_system, ReplayPlayback},
+    LevelData, LevelEnding, Levels,
+};
+use crate::{GameData, GameState, SaveGameData, Settings, UIAssets};
+
+/// In-game time, which only advances with the fixed timestep and so leaves out time spent
+/// paused or loading.
+#[derive(Resource, Default)]
+pub struct SpeedrunTimer {
+    pub level_time: f32,
+    /// Present while playing through every level in order, starting from the first.
+    pub run: Option<CampaignRun>,
+    /// The split recorded when the current run last completed a level.
+    pub last_split: Option<Split>,
+    /// Replays being watched are not timed.
+    active: bool,
+}
+
+#[derive(Default)]
+pub struct CampaignRun {
+    pub time: f32,
+    /// Run time at the end of each completed level.
+    pub splits: Vec<f32>,
+}
+
+pub struct Split {
+    pub time: f32,
+    /// How far ahead (negative) or behind (positive) the best run this split was.
+    pub delta: Option<f32>,
+}
+
+#[derive(Component)]
+struct SpeedrunOverlay;
+
+#[derive(Component)]
+struct SpeedrunLabel;
+
+pub struct SpeedrunPlugin;
+
+impl Plugin for SpeedrunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpeedrunTimer>()
+            .add_systems(
+                OnEnter(GameState::Level),
+                (start_speedrun_timer, spawn_speedrun_overlay),
+            )
+            .add_systems(
+                OnExit(GameState::Level),
+                (record_in_game_time, despawn_speedrun_overlay),
+            )
+            .add_systems(OnEnter(GameState::LevelCompleted), record_split)
+            .add_systems(
+                Update,
+                (
+                    speedrun_label_system,
+                    speedrun_visibility_system.run_if(resource_changed::<Settings>()),
+                )
+                    .run_if(in_state(GameState::Level)),
+            )
+            .add_systems(
+                FixedUpdate,
+                speedrun_timer_system
+                    .after(player_input_system)
+                    .run_if(in_state(GameState::Level)),
+            );
+    }
+}
+
+/// Formats seconds as `m:ss.mmm`.
+pub fn format_time(seconds: f32) -> String {
+    let millis = (seconds.max(0.) * 1000.).round() as u64;
+    format!(
+        "{}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+pub fn format_delta(delta: f32) -> String {
+    let sign = if delta < 0. { "-" } else { "+" };
+    format!("{}{}", sign, format_time(delta.abs()))
+}
+
+pub fn delta_color(delta: f32) -> Color {
+    if delta < 0. {
+        Color::SEA_GREEN
+    } else {
+        Color::CRIMSON
+    }
+}
+
+fn start_speedrun_timer(
+    mut timer: ResMut<SpeedrunTimer>,
+    game_data: Res<GameData>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    let level = game_data.slot().current_level;
+
+    timer.level_time = 0.;
+    timer.last_split = None;
+    timer.active = playback.is_none();
+
+    if !timer.active {
+        timer.run = None;
+    } else if level == 1 {
+        timer.run = Some(CampaignRun::default());
+    } else if timer
+        .run
+        .as_ref()
+        .is_some_and(|run| run.splits.len() != level - 1)
+    {
+        // Playing levels out of order ends the run
+        timer.run = None;
+    }
+}
+
+fn speedrun_timer_system(
+    time: Res<Time<Fixed>>,
+    game_state: Res<NextState<GameState>>,
+    ending: Option<Res<LevelEnding>>,
+    mut timer: ResMut<SpeedrunTimer>,
+    player_query: Query<(), With<Player>>,
+) {
+    // Ticks before the player spawns or after the level has ended are not timed
+    if !timer.active || player_query.is_empty() || ending.is_some() || game_state.0.is_some() {
+        return;
+    }
+
+    let delta = time.delta_seconds();
+    timer.level_time += delta;
+
+    if let Some(run) = timer.run.as_mut() {
+        run.time += delta;
+    }
+}
+
+fn record_in_game_time(
+    mut save_events: EventWriter<SaveGameData>,
+    mut game_data: ResMut<GameData>,
+    timer: Res<SpeedrunTimer>,
+) {
+    if timer.active {
+        game_data.slot_mut().in_game_time += timer.level_time;
+        save_events.send_default();
+    }
+}
+
+pub fn record_split(
+    mut save_events: EventWriter<SaveGameData>,
+    mut timer: ResMut<SpeedrunTimer>,
+    mut game_data: ResMut<GameData>,
+    level_assets: Res<Assets<LevelData>>,
+    levels: Res<Levels>,
+) {
+    let timer = &mut *timer;
+    let Some(run) = timer.run.as_mut() else {
+        return;
+    };
+
+    let best_splits = &game_data.slot().best_splits;
+    timer.last_split = Some(Split {
+        time: run.time,
+        delta: best_splits
+            .get(run.splits.len())
+            .map(|best| run.time - best),
+    });
+    run.splits.push(run.time);
+
+    let last_level = levels
+        .0
+        .iter()
+        .filter(|(_, handle)| level_assets.contains(*handle))
+        .map(|(n, _)| *n)
+        .max()
+        .unwrap_or_default();
+
+    if game_data.slot().current_level == last_level {
+        let slot = game_data.slot_mut();
+        let new_best = slot.best_splits.last().is_none_or(|best| run.time < *best);
+
+        if new_best {
+            slot.best_splits = run.splits.clone();
+            save_events.send_default();
+        }
+    }
+}
+
+fn spawn_speedrun_overlay(
+    mut commands: Commands,
+    ui_assets: Res<UIAssets>,
+    settings: Res<Settings>,
+) {
+    let style = TextStyle {
+        font: ui_assets.font.clone(),
+        font_size: 24.,
+        color: Color::BLACK,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.),
+                    top: Val::Px(50.),
+                    ..Default::default()
+                },
+                visibility: if settings.speedrun_timer {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                },
+                ..Default::default()
+            },
+            SpeedrunOverlay,
+        ))
+        .with_children(|n| {
+            n.spawn((
+                TextBundle::from_sections([
+                    TextSection::new("Level ", style.clone()),
+                    TextSection::new("", style.clone()),
+                    TextSection::new("", style.clone()),
+                    TextSection::new("", style.clone()),
+                ])
+                .with_text_alignment(TextAlignment::Right),
+                SpeedrunLabel,
+            ));
+        });
+}
+
+fn despawn_speedrun_overlay(mut commands: Commands, query: Query<Entity, With<SpeedrunOverlay>>) {
+    if let Ok(entity) = query.get_single() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn speedrun_visibility_system(
+    settings: Res<Settings>,
+    mut query: Query<&mut Visibility, With<SpeedrunOverlay>>,
+) {
+    if let Ok(mut visibility) = query.get_single_mut() {
+        *visibility = if settings.speedrun_timer {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn speedrun_label_system(
+    timer: Res<SpeedrunTimer>,
+    game_data: Res<GameData>,
+    mut query: Query<&mut Text, With<SpeedrunLabel>>,
+) {
+    if let Ok(mut text) = query.get_single_mut() {
+        text.sections[1].value = format_time(timer.level_time);
+
+        match timer.run.as_ref() {
+            Some(run) => {
+                let slot = game_data.slot();
+                text.sections[2].value = format!("\nRun {} ", format_time(run.time));
+
+                // Compares against where the best run was at the end of this level
+                match slot
+                    .best_splits
+                    .get(run.splits.len())
+                    .map(|best| run.time - best)
+                {
+                    Some(delta) => {
+                        text.sections[3].value = format_delta(delta);
+                        text.sections[3].style.color = delta_color(delta);
+                    }
+                    None => text.sections[3].value.clear(),
+                }
+            }
+            None => {
+                text.sections[2].value.clear();
+                text.sections[3].value.clear();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_times_to_the_millisecond() {
+        assert_eq!(format_time(0.), "0:00.000");
+        assert_eq!(format_time(83.4567), "1:23.457");
+        assert_eq!(format_delta(-1.5), "-0:01.500");
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use super::{animation::sprite_animation_system, tuning::GameTuning};
+use crate::GameState;
+
+/// Visual-only scale of a sprite that springs back to normal after being squashed or
+/// stretched. It is applied through the sprite's size rather than the `Transform`, so it never
+/// changes the entity's scale or collision shape.
+#[derive(Component)]
+pub struct SquashStretch {
+    scale: Vec2,
+    velocity: Vec2,
+}
+
+impl Default for SquashStretch {
+    fn default() -> Self {
+        Self {
+            scale: Vec2::ONE,
+            velocity: Vec2::ZERO,
+        }
+    }
+}
+
+impl SquashStretch {
+    /// Flattens the sprite, widening it by as much as it loses in height.
+    pub fn squash(&mut self, amount: f32) {
+        self.scale = Vec2::new(1. + amount, 1. - amount);
+        self.velocity = Vec2::ZERO;
+    }
+
+    /// Stretches the sprite upwards, narrowing it by as much as it gains in height.
+    pub fn stretch(&mut self, amount: f32) {
+        self.squash(-amount);
+    }
+}
+
+pub struct SquashPlugin;
+
+impl Plugin for SquashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            squash_system
+                .after(sprite_animation_system)
+                .run_if(in_state(GameState::Level)),
+        );
+    }
+}
+
+fn squash_system(
+    time: Res<Time>,
+    tuning: Res<GameTuning>,
+    atlases: Res<Assets<TextureAtlas>>,
+    mut query: Query<(
+        &mut SquashStretch,
+        &Handle<TextureAtlas>,
+        &mut TextureAtlasSprite,
+    )>,
+) {
+    // The spring becomes unstable over long frames
+    let delta = time.delta_seconds().min(1. / 30.);
+
+    for (mut squash, atlas, mut sprite) in query.iter_mut() {
+        // Damped spring towards the normal shape, which overshoots a little for a wobble
+        let acceleration = (Vec2::ONE - squash.scale) * tuning.squash.stiffness
+            - squash.velocity * tuning.squash.damping;
+        squash.velocity += acceleration * delta;
+        let velocity = squash.velocity;
+        squash.scale = (squash.scale + velocity * delta).max(Vec2::splat(0.1));
+
+        let Some(frame) = atlases
+            .get(atlas)
+            .and_then(|atlas| atlas.textures.get(sprite.index))
+        else {
+            continue;
+        };
+
+        sprite.custom_size = Some(frame.size() * squash.scale);
+        // Keeps the bottom of the sprite in place so that squashing does not lift the feet
+        sprite.anchor = Anchor::Custom(Vec2::new(0., 0.5 / squash.scale.y - 0.5));
+    }
+}
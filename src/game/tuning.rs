@@ -0,0 +1,180 @@
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt, LoadState},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use serde::Deserialize;
+
+use super::JsonLoaderError;
+
+/// The tuning file as it was when the game was built, used until the asset has loaded, or if it
+/// fails to.
+const BUILT_IN_TUNING: &str = include_str!("../../assets/tuning.tuning.json");
+
+/// Gameplay constants that designers can tweak without recompiling.
+///
+/// The values are loaded from `assets/tuning.tuning.json` and copied into a resource of the same
+/// type whenever the file is loaded or modified. Enable the `hot_reload` feature to pick up
+/// changes while the game is running. Until then, the resource holds the values the game was
+/// built with.
+#[derive(Resource, Deserialize, TypeUuid, Asset, TypePath, Clone)]
+#[uuid = "6d0f8b3c-5a6e-4b8f-a3f4-1c2f0e9d7b41"]
+pub struct GameTuning {
+    pub sprite_scale: f32,
+    pub gravity: f32,
+    pub player: PlayerTuning,
+    pub camera: CameraTuning,
+    pub squash: SquashTuning,
+}
+
+impl Default for GameTuning {
+    fn default() -> Self {
+        serde_json::from_str(BUILT_IN_TUNING).expect("Built-in tuning should be valid")
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PlayerTuning {
+    pub run_speed: f32,
+    pub jump_speed: f32,
+    pub ground_acceleration: f32,
+    pub ground_deceleration: f32,
+    pub air_acceleration: f32,
+    pub air_deceleration: f32,
+    pub wall_slide_speed: f32,
+    pub wall_jump_speed: f32,
+    /// Falling faster than this before landing shakes the camera.
+    pub hard_landing_speed: f32,
+    /// Falling faster than this before landing kicks up dust.
+    pub landing_dust_speed: f32,
+}
+
+/// Smoothing values are rates per second, where higher values catch up faster.
+#[derive(Deserialize, Clone)]
+pub struct CameraTuning {
+    pub smoothing: f32,
+    /// Half the size of the area the player can move in without moving the camera.
+    pub dead_zone: Vec2,
+    pub look_ahead: f32,
+    pub look_ahead_smoothing: f32,
+    /// Used instead of `smoothing` for vertical movement while the player is rising.
+    pub jump_vertical_smoothing: f32,
+    /// How far past the outermost objects of a level the camera is allowed to show.
+    pub bounds_margin: f32,
+    pub max_shake_offset: f32,
+    /// Maximum camera rotation while shaking, in radians.
+    pub max_shake_angle: f32,
+    /// Trauma lost per second.
+    pub trauma_decay: f32,
+    pub zoom_pulse_decay: f32,
+}
+
+/// Squash and stretch of the player sprite, which does not affect collision.
+#[derive(Deserialize, Clone)]
+pub struct SquashTuning {
+    /// How strongly the sprite springs back to its normal shape.
+    pub stiffness: f32,
+    pub damping: f32,
+    pub jump_stretch: f32,
+    /// Squash from landing at `hard_landing_speed`. Softer landings squash proportionally less.
+    pub landing_squash: f32,
+    /// Landing slower than this, such as stepping off a low ledge, does not squash the sprite.
+    pub min_landing_speed: f32,
+}
+
+#[derive(Resource)]
+struct GameTuningHandle(Handle<GameTuning>);
+
+#[derive(Default)]
+struct GameTuningLoader;
+
+impl AssetLoader for GameTuningLoader {
+    type Asset = GameTuning;
+    type Settings = ();
+    type Error = JsonLoaderError;
+
+    fn extensions(&self) -> &[&str] {
+        &["tuning.json"]
+    }
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let data = serde_json::from_slice(&bytes)?;
+            Ok(data)
+        })
+    }
+}
+
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<GameTuning>()
+            .init_asset_loader::<GameTuningLoader>()
+            .init_resource::<GameTuning>()
+            .add_systems(Startup, load_tuning)
+            .add_systems(
+                Update,
+                (
+                    update_tuning.run_if(on_event::<AssetEvent<GameTuning>>()),
+                    report_tuning_error,
+                ),
+            );
+    }
+}
+
+fn load_tuning(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(GameTuningHandle(asset_server.load("tuning.tuning.json")));
+}
+
+fn update_tuning(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<GameTuning>>,
+    tuning_assets: Res<Assets<GameTuning>>,
+    tuning_handle: Res<GameTuningHandle>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }
+                if *id == tuning_handle.0.id() =>
+            {
+                if let Some(tuning) = tuning_assets.get(*id) {
+                    commands.insert_resource(tuning.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The asset server logs why the file failed to load, so this only points out what happens next.
+fn report_tuning_error(
+    asset_server: Res<AssetServer>,
+    tuning_handle: Res<GameTuningHandle>,
+    mut reported: Local<bool>,
+) {
+    if !*reported && asset_server.load_state(&tuning_handle.0) == LoadState::Failed {
+        error!("Could not load tuning, using the values the game was built with");
+        *reported = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_built_in_tuning() {
+        let tuning = GameTuning::default();
+
+        assert!(tuning.sprite_scale > 0.);
+        assert!(tuning.player.run_speed > 0.);
+    }
+}
@@ -89,14 +89,11 @@ fn try_again_system(
     mut events: EventWriter<SpawnScreenFader>,
     query: Query<&Interaction, (With<TryAgainButton>, Changed<Interaction>)>,
 ) {
-    if let Ok(interaction) = query.get_single() {
-        match *interaction {
-            Interaction::Pressed => events.send(SpawnScreenFader {
-                fade_color: Color::BLACK,
-                fade_time: 0.8,
-                next_state: GameState::Level,
-            }),
-            _ => {}
-        }
+    if let Ok(Interaction::Pressed) = query.get_single() {
+        events.send(SpawnScreenFader {
+            fade_color: Color::BLACK,
+            fade_time: 0.8,
+            next_state: GameState::Level,
+        });
     }
 }
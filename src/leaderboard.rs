@@ -0,0 +1,518 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{LevelData, LevelResult, LevelSeed, Levels},
+    main_menu::MenuScreen,
+    save::{save_dir, write_atomic},
+    save_slot_menu::MAX_NAME_LENGTH,
+    spawn_button, GameData, GameState, UIAssets,
+};
+
+const MAX_ENTRIES: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub time: f32,
+    pub date: String,
+    pub seed: u64,
+}
+
+impl LeaderboardEntry {
+    /// Imported leaderboards can come from anywhere, so only accept entries the game could have
+    /// recorded itself.
+    fn is_valid(&self) -> bool {
+        self.time.is_finite()
+            && self.time > 0.
+            && !self.name.is_empty()
+            && self.name.chars().count() <= MAX_NAME_LENGTH
+            && !self.name.chars().any(char::is_control)
+            && is_date(&self.date)
+    }
+}
+
+/// Fastest completion times for a single level. This is also the import/export format, so
+/// leaderboards can be shared as plain JSON files.
+#[derive(Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub level: usize,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    fn new(level: usize) -> Self {
+        Self {
+            level,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Inserts an entry in order of completion time, returning its rank if it made the board.
+    fn insert(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        if self.entries.contains(&entry) {
+            return None;
+        }
+
+        let rank = self.entries.partition_point(|e| e.time <= entry.time);
+
+        if rank >= MAX_ENTRIES {
+            return None;
+        }
+
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Leaderboards(pub HashMap<usize, Leaderboard>);
+
+#[derive(Resource, Default)]
+struct LeaderboardMenu {
+    level: usize,
+    message: String,
+}
+
+#[derive(Component)]
+struct LeaderboardScreen;
+
+#[derive(Component)]
+enum ButtonType {
+    Previous,
+    Next,
+    Export,
+    Import,
+    Back,
+}
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LeaderboardMenu>()
+            .add_systems(Startup, load_leaderboards)
+            .add_systems(
+                OnEnter(GameState::LevelCompleted),
+                record_leaderboard_entry.run_if(|result: Res<LevelResult>| !result.replay),
+            )
+            .add_systems(OnEnter(MenuScreen::Leaderboard), open_leaderboard_screen)
+            .add_systems(OnExit(MenuScreen::Leaderboard), despawn_leaderboard_screen)
+            .add_systems(
+                Update,
+                (
+                    button_action_system,
+                    (despawn_leaderboard_screen, spawn_leaderboard_screen)
+                        .chain()
+                        .run_if(
+                            resource_changed::<Leaderboards>()
+                                .or_else(resource_changed::<LeaderboardMenu>()),
+                        ),
+                )
+                    .chain()
+                    .run_if(in_state(MenuScreen::Leaderboard)),
+            );
+    }
+}
+
+fn leaderboard_dir() -> PathBuf {
+    save_dir().join("leaderboards")
+}
+
+fn leaderboard_path(level: usize) -> PathBuf {
+    leaderboard_dir().join(format!("level{}.json", level))
+}
+
+fn read_leaderboard(path: &Path) -> Result<Leaderboard, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+}
+
+fn write_leaderboard(path: &Path, leaderboard: &Leaderboard) {
+    let result = serde_json::to_vec_pretty(leaderboard)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| write_atomic(path, &bytes, false).map_err(|e| e.to_string()));
+
+    if let Err(e) = result {
+        error!("Could not write leaderboard {}: {}", path.display(), e);
+    }
+}
+
+/// Reads every leaderboard file in a directory, skipping any that cannot be parsed.
+fn read_leaderboard_dir(dir: &Path) -> Vec<Leaderboard> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| match read_leaderboard(&path) {
+            Ok(leaderboard) => Some(leaderboard),
+            Err(e) => {
+                error!("Could not read leaderboard {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Today's date in `YYYY-MM-DD` form, in UTC.
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Converts days since the Unix epoch into a proleptic Gregorian `(year, month, day)`, using
+/// Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    (year, month, day)
+}
+
+/// Whether a string is a date in the `YYYY-MM-DD` form written by `today`.
+fn is_date(date: &str) -> bool {
+    date.len() == 10
+        && date.bytes().enumerate().all(|(i, byte)| match i {
+            4 | 7 => byte == b'-',
+            _ => byte.is_ascii_digit(),
+        })
+}
+
+fn load_leaderboards(mut commands: Commands) {
+    let leaderboards = read_leaderboard_dir(&leaderboard_dir())
+        .into_iter()
+        .map(|leaderboard| (leaderboard.level, leaderboard))
+        .collect();
+
+    commands.insert_resource(Leaderboards(leaderboards));
+}
+
+pub fn record_leaderboard_entry(
+    mut result: ResMut<LevelResult>,
+    mut leaderboards: ResMut<Leaderboards>,
+    game_data: Res<GameData>,
+    seed: Res<LevelSeed>,
+) {
+    let leaderboard = leaderboards
+        .0
+        .entry(result.level)
+        .or_insert_with(|| Leaderboard::new(result.level));
+
+    result.leaderboard_rank = leaderboard.insert(LeaderboardEntry {
+        name: game_data.slot().name.clone(),
+        time: result.time,
+        date: today(),
+        seed: seed.0,
+    });
+
+    if result.leaderboard_rank.is_some() {
+        write_leaderboard(&leaderboard_path(result.level), leaderboard);
+    }
+}
+
+/// Spawns a table of leaderboard entries, highlighting the entry at `highlight`.
+pub fn spawn_leaderboard_entries(
+    parent: &mut ChildBuilder,
+    ui_assets: &UIAssets,
+    leaderboard: Option<&Leaderboard>,
+    highlight: Option<usize>,
+    limit: usize,
+) {
+    let style = TextStyle {
+        font: ui_assets.font.clone(),
+        font_size: 26.,
+        color: Color::BLACK,
+    };
+
+    let entries = leaderboard.map_or(&[][..], |l| &l.entries[..]);
+
+    if entries.is_empty() {
+        parent.spawn(TextBundle::from_section("No times yet", style));
+        return;
+    }
+
+    for (rank, entry) in entries.iter().enumerate().take(limit) {
+        let color = if Some(rank) == highlight {
+            Color::SEA_GREEN
+        } else {
+            Color::BLACK
+        };
+
+        parent.spawn(TextBundle::from_section(
+            format!(
+                "{}. {}  {:.2}s  {}  seed {}",
+                rank + 1,
+                entry.name,
+                entry.time,
+                entry.date,
+                entry.seed
+            ),
+            TextStyle {
+                color,
+                ..style.clone()
+            },
+        ));
+    }
+}
+
+fn open_leaderboard_screen(mut menu: ResMut<LeaderboardMenu>, game_data: Res<GameData>) {
+    menu.level = game_data.slot().current_level;
+    menu.message.clear();
+}
+
+fn spawn_leaderboard_screen(
+    mut commands: Commands,
+    ui_assets: Res<UIAssets>,
+    leaderboards: Res<Leaderboards>,
+    menu: Res<LeaderboardMenu>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    display: Display::Flex,
+                    row_gap: Val::Px(8.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            LeaderboardScreen,
+        ))
+        .with_children(|n| {
+            n.spawn(NodeBundle {
+                style: Style {
+                    column_gap: Val::Px(8.),
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with_children(|row| {
+                spawn_button(row, &ui_assets, "<", ButtonType::Previous);
+                row.spawn(TextBundle::from_section(
+                    format!("Level {} Leaderboard", menu.level),
+                    TextStyle {
+                        font: ui_assets.font.clone(),
+                        font_size: 50.,
+                        color: Color::BLACK,
+                    },
+                ));
+                spawn_button(row, &ui_assets, ">", ButtonType::Next);
+            });
+
+            spawn_leaderboard_entries(
+                n,
+                &ui_assets,
+                leaderboards.0.get(&menu.level),
+                None,
+                MAX_ENTRIES,
+            );
+
+            n.spawn(TextBundle::from_section(
+                menu.message.clone(),
+                TextStyle {
+                    font: ui_assets.font.clone(),
+                    font_size: 20.,
+                    color: Color::DARK_GRAY,
+                },
+            ));
+
+            n.spawn(NodeBundle {
+                style: Style {
+                    column_gap: Val::Px(8.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with_children(|row| {
+                spawn_button(row, &ui_assets, "Export", ButtonType::Export);
+                spawn_button(row, &ui_assets, "Import", ButtonType::Import);
+                spawn_button(row, &ui_assets, "Back", ButtonType::Back);
+            });
+        });
+}
+
+fn despawn_leaderboard_screen(
+    mut commands: Commands,
+    query: Query<Entity, With<LeaderboardScreen>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn button_action_system(
+    mut menu_screen: ResMut<NextState<MenuScreen>>,
+    mut menu: ResMut<LeaderboardMenu>,
+    mut leaderboards: ResMut<Leaderboards>,
+    level_assets: Res<Assets<LevelData>>,
+    levels: Res<Levels>,
+    query: Query<(&ButtonType, &Interaction), Changed<Interaction>>,
+) {
+    for (btn, interaction) in query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let level_available = |level: &usize| {
+            levels
+                .0
+                .get(level)
+                .is_some_and(|handle| level_assets.contains(handle))
+        };
+
+        match *btn {
+            ButtonType::Previous => {
+                if let Some(level) = (1..menu.level).rev().find(level_available) {
+                    menu.level = level;
+                    menu.message.clear();
+                }
+            }
+            ButtonType::Next => {
+                if let Some(level) = (menu.level + 1..=levels.0.len()).find(level_available) {
+                    menu.level = level;
+                    menu.message.clear();
+                }
+            }
+            ButtonType::Export => {
+                let path = save_dir()
+                    .join("exports")
+                    .join(format!("level{}.json", menu.level));
+                let leaderboard = leaderboards.0.get(&menu.level);
+
+                menu.message = match leaderboard {
+                    Some(leaderboard) => {
+                        write_leaderboard(&path, leaderboard);
+                        format!("Exported to {}", path.display())
+                    }
+                    None => "Nothing to export yet".to_owned(),
+                };
+            }
+            ButtonType::Import => {
+                let dir = save_dir().join("imports");
+                let mut imported = 0;
+                let mut rejected = 0;
+
+                for import in read_leaderboard_dir(&dir) {
+                    if !level_available(&import.level) {
+                        rejected += import.entries.len();
+                        continue;
+                    }
+
+                    let (valid, invalid): (Vec<_>, Vec<_>) = import
+                        .entries
+                        .into_iter()
+                        .partition(|entry| entry.is_valid());
+                    rejected += invalid.len();
+
+                    let leaderboard = leaderboards
+                        .0
+                        .entry(import.level)
+                        .or_insert_with(|| Leaderboard::new(import.level));
+
+                    let added = valid
+                        .into_iter()
+                        .filter_map(|entry| leaderboard.insert(entry))
+                        .count();
+
+                    if added > 0 {
+                        write_leaderboard(&leaderboard_path(import.level), leaderboard);
+                        imported += added;
+                    }
+                }
+
+                menu.message = format!("Imported {} times from {}", imported, dir.display());
+                if rejected > 0 {
+                    menu.message += &format!(", skipped {} invalid", rejected);
+                }
+            }
+            ButtonType::Back => menu_screen.set(MenuScreen::LevelSelect),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, time: f32) -> LeaderboardEntry {
+        LeaderboardEntry {
+            name: name.to_owned(),
+            time,
+            date: "2023-12-01".to_owned(),
+            seed: 0,
+        }
+    }
+
+    #[test]
+    fn keeps_fastest_entries_in_order() {
+        let mut leaderboard = Leaderboard::new(1);
+
+        for i in 0..MAX_ENTRIES {
+            leaderboard.insert(entry("slow", 20. + i as f32));
+        }
+
+        assert_eq!(leaderboard.insert(entry("fast", 10.)), Some(0));
+        assert_eq!(leaderboard.insert(entry("slowest", 100.)), None);
+        assert_eq!(leaderboard.insert(entry("fast", 10.)), None);
+        assert_eq!(leaderboard.entries.len(), MAX_ENTRIES);
+        assert_eq!(leaderboard.entries[0].name, "fast");
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        assert!(entry("fast", 10.).is_valid());
+        assert!(!entry("", 10.).is_valid());
+        assert!(!entry("a name far too long for a save slot", 10.).is_valid());
+        assert!(!entry("fast", 0.).is_valid());
+        assert!(!entry("fast", -1.).is_valid());
+        assert!(!entry("fast", f32::NAN).is_valid());
+        assert!(!entry("fast", f32::INFINITY).is_valid());
+
+        let mut bad_date = entry("fast", 10.);
+        bad_date.date = "yesterday".to_owned();
+        assert!(!bad_date.is_valid());
+    }
+
+    #[test]
+    fn converts_days_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19782), (2024, 2, 29));
+        assert_eq!(civil_from_days(19783), (2024, 3, 1));
+        assert!(is_date(&today()));
+    }
+}
@@ -0,0 +1,268 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        record_completion,
+        replay::{Replay, ReplayPlayback},
+        speedrun::{delta_color, format_delta, format_time, record_split, SpeedrunTimer},
+        LevelData, LevelResult, Levels,
+    },
+    leaderboard::{record_leaderboard_entry, spawn_leaderboard_entries, Leaderboards},
+    spawn_button, GameData, GameState, SaveGameData, Settings, SpawnScreenFader, UIAssets,
+};
+
+const LEADERBOARD_ENTRIES_SHOWN: usize = 5;
+
+#[derive(Component)]
+struct LevelCompleteMenu;
+
+#[derive(Component)]
+enum ButtonType {
+    NextLevel,
+    Retry,
+    WatchReplay,
+    MainMenu,
+}
+
+pub struct LevelCompletePlugin;
+
+impl Plugin for LevelCompletePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::LevelCompleted),
+            spawn_level_complete_menu
+                .after(record_completion)
+                .after(record_leaderboard_entry)
+                .after(record_split),
+        )
+        .add_systems(
+            OnExit(GameState::LevelCompleted),
+            despawn_level_complete_menu,
+        )
+        .add_systems(
+            Update,
+            button_action_system.run_if(in_state(GameState::LevelCompleted)),
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_level_complete_menu(
+    mut commands: Commands,
+    ui_assets: Res<UIAssets>,
+    result: Res<LevelResult>,
+    leaderboards: Res<Leaderboards>,
+    speedrun: Res<SpeedrunTimer>,
+    settings: Res<Settings>,
+    game_data: Res<GameData>,
+    level_assets: Res<Assets<LevelData>>,
+    levels: Res<Levels>,
+) {
+    let slot = game_data.slot();
+    let record = slot.level_records.get(&result.level);
+    let has_next_level = levels
+        .0
+        .get(&(result.level + 1))
+        .is_some_and(|handle| level_assets.contains(handle));
+
+    let style = TextStyle {
+        font: ui_assets.font.clone(),
+        font_size: 32.,
+        color: Color::BLACK,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    display: Display::Flex,
+                    row_gap: Val::Px(8.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(Color::ANTIQUE_WHITE),
+                ..Default::default()
+            },
+            LevelCompleteMenu,
+        ))
+        .with_children(|n| {
+            n.spawn(TextBundle::from_section(
+                if result.replay {
+                    format!("Level {} Replay", result.level)
+                } else {
+                    format!("Level {} Complete!", result.level)
+                },
+                TextStyle {
+                    font_size: 50.,
+                    ..style.clone()
+                },
+            ));
+
+            let best_time = record.and_then(|r| r.best_time).unwrap_or(result.time);
+            n.spawn(TextBundle::from_section(
+                format!("Time: {:.2}s (Best: {:.2}s)", result.time, best_time),
+                style.clone(),
+            ));
+
+            n.spawn(TextBundle::from_section(
+                format!("Pills: {}", result.pills_collected),
+                style.clone(),
+            ));
+
+            if result.bonus_total > 0 {
+                n.spawn(TextBundle::from_section(
+                    format!(
+                        "Bonus Pills: {}/{}",
+                        result.bonus_collected, result.bonus_total
+                    ),
+                    style.clone(),
+                ));
+            }
+
+            let best_score = record.map_or(result.score, |r| r.best_score);
+            let score_text = if result.new_best {
+                format!("Score: {} (New Best!)", result.score)
+            } else {
+                format!("Score: {} (Best: {})", result.score, best_score)
+            };
+            n.spawn(TextBundle::from_section(score_text, style.clone()));
+
+            if result.max_stars > 0 {
+                n.spawn(TextBundle::from_section(
+                    format!("Stars: {}/{}", result.stars, result.max_stars),
+                    style.clone(),
+                ));
+            }
+
+            if let Some(split) = speedrun
+                .last_split
+                .as_ref()
+                .filter(|_| settings.speedrun_timer)
+            {
+                n.spawn(TextBundle::from_sections([
+                    TextSection::new(
+                        format!("Split: {} ", format_time(split.time)),
+                        style.clone(),
+                    ),
+                    TextSection::new(
+                        split.delta.map(format_delta).unwrap_or_default(),
+                        TextStyle {
+                            color: split.delta.map_or(Color::BLACK, delta_color),
+                            ..style.clone()
+                        },
+                    ),
+                ]));
+            }
+
+            if let Some(rank) = result.leaderboard_rank {
+                n.spawn(TextBundle::from_section(
+                    format!("Leaderboard: #{}", rank + 1),
+                    style.clone(),
+                ));
+            }
+
+            spawn_leaderboard_entries(
+                n,
+                &ui_assets,
+                leaderboards.0.get(&result.level),
+                result.leaderboard_rank,
+                LEADERBOARD_ENTRIES_SHOWN,
+            );
+
+            n.spawn(NodeBundle {
+                style: Style {
+                    column_gap: Val::Px(8.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with_children(|row| {
+                if has_next_level {
+                    spawn_button(row, &ui_assets, "Next Level", ButtonType::NextLevel);
+                }
+
+                spawn_button(row, &ui_assets, "Retry", ButtonType::Retry);
+                spawn_button(row, &ui_assets, "Watch Replay", ButtonType::WatchReplay);
+                spawn_button(row, &ui_assets, "Main Menu", ButtonType::MainMenu);
+            });
+        });
+}
+
+fn despawn_level_complete_menu(
+    mut commands: Commands,
+    query: Query<Entity, With<LevelCompleteMenu>>,
+) {
+    if let Ok(entity) = query.get_single() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn button_action_system(
+    mut commands: Commands,
+    mut fader_events: EventWriter<SpawnScreenFader>,
+    mut save_events: EventWriter<SaveGameData>,
+    mut game_data: ResMut<GameData>,
+    result: Res<LevelResult>,
+    level_assets: Res<Assets<LevelData>>,
+    levels: Res<Levels>,
+    query: Query<(&ButtonType, &Interaction), Changed<Interaction>>,
+    button_query: Query<Entity, With<ButtonType>>,
+) {
+    for (btn, interaction) in query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let next_state = match *btn {
+            ButtonType::NextLevel => {
+                game_data.slot_mut().current_level = result.level + 1;
+                save_events.send_default();
+                Some(GameState::Level)
+            }
+            ButtonType::Retry => Some(GameState::Level),
+            ButtonType::WatchReplay => {
+                let level_data = level_assets.get(&levels.0[&result.level]).unwrap();
+
+                match Replay::load(&Replay::path(result.level)) {
+                    // Inputs only reproduce the run on the exact level they were recorded on
+                    Ok(replay) if replay.level_hash != level_data.hash => {
+                        warn!(
+                            "Replay for level {} is from a different version of the level",
+                            result.level
+                        );
+                        None
+                    }
+                    Ok(replay) => {
+                        commands.insert_resource(ReplayPlayback::new(&replay));
+                        Some(GameState::Level)
+                    }
+                    Err(e) => {
+                        error!("Could not load replay for level {}: {}", result.level, e);
+                        None
+                    }
+                }
+            }
+            ButtonType::MainMenu => Some(GameState::MainMenu),
+        };
+
+        if let Some(next_state) = next_state {
+            fader_events.send(SpawnScreenFader {
+                fade_color: Color::ANTIQUE_WHITE,
+                fade_time: 0.8,
+                next_state,
+            });
+
+            // The menu stays visible while fading out, so stop it from taking any more presses
+            for entity in button_query.iter() {
+                commands.entity(entity).remove::<Interaction>();
+            }
+
+            return;
+        }
+    }
+}
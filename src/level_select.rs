@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{LevelData, Levels},
+    main_menu::MenuScreen,
+    spawn_button, GameData, GameState, SaveGameData, SpawnScreenFader, UIAssets,
+};
+
+#[derive(Component)]
+struct LevelSelectMenu;
+
+#[derive(Component)]
+enum ButtonType {
+    Level(usize),
+    Locked,
+    Leaderboards,
+    Back,
+}
+
+pub struct LevelSelectPlugin;
+
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(MenuScreen::LevelSelect), spawn_level_select)
+            .add_systems(OnExit(MenuScreen::LevelSelect), despawn_level_select)
+            .add_systems(
+                Update,
+                button_action_system.run_if(in_state(MenuScreen::LevelSelect)),
+            );
+    }
+}
+
+fn spawn_level_select(
+    mut commands: Commands,
+    ui_assets: Res<UIAssets>,
+    game_data: Res<GameData>,
+    level_assets: Res<Assets<LevelData>>,
+    levels: Res<Levels>,
+) {
+    let slot = game_data.slot();
+    let style = TextStyle {
+        font: ui_assets.font.clone(),
+        font_size: 24.,
+        color: Color::BLACK,
+    };
+
+    // Levels that failed to load are left out of the grid
+    let mut level_numbers: Vec<usize> = levels
+        .0
+        .iter()
+        .filter(|(_, handle)| level_assets.contains(*handle))
+        .map(|(n, _)| *n)
+        .collect();
+    level_numbers.sort();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    display: Display::Flex,
+                    row_gap: Val::Px(8.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            LevelSelectMenu,
+        ))
+        .with_children(|n| {
+            n.spawn(TextBundle::from_section(
+                "Select Level",
+                TextStyle {
+                    font: ui_assets.font.clone(),
+                    font_size: 50.,
+                    color: Color::BLACK,
+                },
+            ));
+
+            n.spawn(NodeBundle {
+                style: Style {
+                    max_width: Val::Px(720.),
+                    flex_wrap: FlexWrap::Wrap,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.),
+                    column_gap: Val::Px(8.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with_children(|grid| {
+                for level in level_numbers {
+                    let level_data = level_assets.get(&levels.0[&level]).unwrap();
+                    let record = slot.level_records.get(&level);
+                    let unlocked = level <= slot.unlocked_level;
+
+                    let best_time = match record.and_then(|r| r.best_time) {
+                        Some(time) => format!("Best: {:.2}s", time),
+                        None => "Best: -".to_owned(),
+                    };
+                    let pills = match record {
+                        Some(r) if r.most_pills as usize >= level_data.pills.len() => "All pills",
+                        _ => "",
+                    };
+
+                    grid.spawn((
+                        ButtonBundle {
+                            image: UiImage::new(ui_assets.button.clone()),
+                            background_color: BackgroundColor(if unlocked {
+                                Color::WHITE
+                            } else {
+                                Color::GRAY
+                            }),
+                            style: Style {
+                                width: Val::Px(160.),
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::all(Val::Px(12.)),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        if unlocked {
+                            ButtonType::Level(level)
+                        } else {
+                            ButtonType::Locked
+                        },
+                    ))
+                    .with_children(|cell| {
+                        cell.spawn(TextBundle::from_section(
+                            format!("Level {}", level),
+                            TextStyle {
+                                font_size: 32.,
+                                ..style.clone()
+                            },
+                        ));
+
+                        if unlocked {
+                            cell.spawn(TextBundle::from_section(best_time, style.clone()));
+                            cell.spawn(TextBundle::from_section(pills, style.clone()));
+                        } else {
+                            cell.spawn(TextBundle::from_section("Locked", style.clone()));
+                        }
+                    });
+                }
+            });
+
+            n.spawn(NodeBundle {
+                style: Style {
+                    column_gap: Val::Px(8.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with_children(|row| {
+                spawn_button(row, &ui_assets, "Leaderboards", ButtonType::Leaderboards);
+                spawn_button(row, &ui_assets, "Back", ButtonType::Back);
+            });
+        });
+}
+
+fn despawn_level_select(mut commands: Commands, query: Query<Entity, With<LevelSelectMenu>>) {
+    if let Ok(entity) = query.get_single() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn button_action_system(
+    mut fader_events: EventWriter<SpawnScreenFader>,
+    mut save_events: EventWriter<SaveGameData>,
+    mut menu_screen: ResMut<NextState<MenuScreen>>,
+    mut game_data: ResMut<GameData>,
+    query: Query<(&ButtonType, &Interaction), Changed<Interaction>>,
+) {
+    for (btn, interaction) in query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match *btn {
+            ButtonType::Level(level) => {
+                game_data.slot_mut().current_level = level;
+                save_events.send_default();
+                fader_events.send(SpawnScreenFader {
+                    fade_color: Color::ANTIQUE_WHITE,
+                    fade_time: 0.8,
+                    next_state: GameState::Level,
+                });
+            }
+            ButtonType::Locked => {}
+            ButtonType::Leaderboards => menu_screen.set(MenuScreen::Leaderboard),
+            ButtonType::Back => menu_screen.set(MenuScreen::SaveSlots),
+        }
+    }
+}
@@ -1,10 +1,25 @@
-use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*};
+use std::collections::HashMap;
+
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::texture::{
+        ImageAddressMode, ImageLoaderSettings, ImageSampler, ImageSamplerDescriptor,
+    },
+};
 use bevy_kira_audio::prelude::*;
 use bincode::{Decode, Encode};
 use components::ScreenFade;
-use game::GamePlugin;
+use game::{animation::AnimationLibrary, GamePlugin};
 use game_over::GameOverPlugin;
+use leaderboard::LeaderboardPlugin;
+use level_complete::LevelCompletePlugin;
+use level_select::LevelSelectPlugin;
 use main_menu::MainMenuPlugin;
+use pause_menu::PauseMenuPlugin;
+use save::SavePlugin;
+use save_slot_menu::SaveSlotMenuPlugin;
+use settings::SettingsPlugin;
 
 #[cfg(feature = "inspector")]
 use bevy_inspector_egui::quick::{ResourceInspectorPlugin, WorldInspectorPlugin};
@@ -12,7 +27,14 @@ use bevy_inspector_egui::quick::{ResourceInspectorPlugin, WorldInspectorPlugin};
 mod components;
 mod game;
 mod game_over;
+mod leaderboard;
+mod level_complete;
+mod level_select;
 mod main_menu;
+mod pause_menu;
+mod save;
+mod save_slot_menu;
+mod settings;
 
 #[derive(Component)]
 struct MainCamera;
@@ -24,6 +46,7 @@ pub enum GameState {
     Level,
     GameOver,
     LevelCompleted,
+    Restarting,
 }
 
 #[derive(Resource)]
@@ -35,13 +58,10 @@ struct UIAssets {
 
 #[derive(Resource)]
 struct GameAssets {
-    player_idle: Handle<TextureAtlas>,
-    player_run: Handle<TextureAtlas>,
-    player_jump: Handle<TextureAtlas>,
-    player_fall: Handle<TextureAtlas>,
+    player_animations: Handle<AnimationLibrary>,
     platform: Handle<Image>,
-    pill: Handle<TextureAtlas>,
-    patient: Handle<TextureAtlas>,
+    pill_animations: Handle<AnimationLibrary>,
+    patient_animations: Handle<AnimationLibrary>,
 }
 
 #[derive(Resource)]
@@ -49,16 +69,126 @@ struct AudioAssets {
     bg_music: Handle<bevy_kira_audio::AudioSource>,
     player_jump: Handle<bevy_kira_audio::AudioSource>,
     pill_collect: Handle<bevy_kira_audio::AudioSource>,
+    footstep: Handle<bevy_kira_audio::AudioSource>,
 }
 
+/// Always holds at least one slot, and `active_slot` always refers to one of them.
 #[derive(Resource, Encode, Decode, Reflect)]
 struct GameData {
-    current_level: usize,
+    slots: Vec<SaveSlot>,
+    active_slot: usize,
 }
 
 impl Default for GameData {
     fn default() -> Self {
-        Self { current_level: 1 }
+        Self {
+            slots: vec![SaveSlot::new("Save 1")],
+            active_slot: 0,
+        }
+    }
+}
+
+impl GameData {
+    /// Restores the slot invariants on data loaded from disk.
+    fn repair(&mut self) {
+        if self.slots.is_empty() {
+            self.slots.push(SaveSlot::new("Save 1"));
+        }
+
+        self.active_slot = self.active_slot.min(self.slots.len() - 1);
+    }
+
+    /// The first name of the form "Save N" that no slot is using yet.
+    fn unused_slot_name(&self) -> String {
+        (1..)
+            .map(|n| format!("Save {}", n))
+            .find(|name| self.slots.iter().all(|slot| slot.name != *name))
+            .unwrap()
+    }
+
+    fn slot(&self) -> &SaveSlot {
+        &self.slots[self.active_slot]
+    }
+
+    fn slot_mut(&mut self) -> &mut SaveSlot {
+        &mut self.slots[self.active_slot]
+    }
+}
+
+#[derive(Encode, Decode, Reflect)]
+struct SaveSlot {
+    name: String,
+    current_level: usize,
+    unlocked_level: usize,
+    level_records: HashMap<usize, LevelRecord>,
+    /// Total time spent playing levels, not counting time spent paused.
+    in_game_time: f32,
+    /// Time at the end of each level in the fastest run through every level.
+    best_splits: Vec<f32>,
+}
+
+impl SaveSlot {
+    fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            current_level: 1,
+            unlocked_level: 1,
+            level_records: HashMap::new(),
+            in_game_time: 0.,
+            best_splits: Vec::new(),
+        }
+    }
+
+    /// Records a completed attempt at the current level, keeping only the best results and
+    /// unlocking the level after it. Returns whether the attempt set a new best score.
+    fn record_completion(
+        &mut self,
+        time: f32,
+        pills_collected: u32,
+        score: u32,
+        stars: u8,
+    ) -> bool {
+        let record = self.level_records.entry(self.current_level).or_default();
+        let new_best = score > record.best_score;
+
+        record.best_time = Some(record.best_time.map_or(time, |best| best.min(time)));
+        record.most_pills = record.most_pills.max(pills_collected);
+        record.best_score = record.best_score.max(score);
+        record.stars = record.stars.max(stars);
+
+        self.unlocked_level = self.unlocked_level.max(self.current_level + 1);
+        new_best
+    }
+}
+
+#[derive(Encode, Decode, Reflect, Default)]
+struct LevelRecord {
+    best_time: Option<f32>,
+    most_pills: u32,
+    best_score: u32,
+    stars: u8,
+}
+
+#[derive(Resource, Encode, Decode)]
+struct Settings {
+    music: bool,
+    sound_effects: bool,
+    ghost: bool,
+    speedrun_timer: bool,
+    screen_shake: bool,
+    minimap: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music: true,
+            sound_effects: true,
+            ghost: true,
+            speedrun_timer: false,
+            screen_shake: true,
+            minimap: false,
+        }
     }
 }
 
@@ -102,23 +232,21 @@ fn main() {
     .add_plugins(MainMenuPlugin)
     .add_plugins(GamePlugin)
     .add_plugins(GameOverPlugin)
-    .add_systems(
-        Startup,
-        (
-            setup_camera,
-            setup_assets,
-            load_game_data,
-            setup_audio_channels,
-        ),
-    )
-    .add_systems(Update, save_game_data.run_if(on_event::<SaveGameData>()))
-    .add_systems(OnEnter(GameState::LevelCompleted), next_level_system)
+    .add_plugins(LevelCompletePlugin)
+    .add_plugins(PauseMenuPlugin)
+    .add_plugins(SavePlugin)
+    .add_plugins(SaveSlotMenuPlugin)
+    .add_plugins(SettingsPlugin)
+    .add_plugins(LevelSelectPlugin)
+    .add_plugins(LeaderboardPlugin)
+    .add_systems(Startup, (setup_camera, setup_assets))
     .add_systems(
         Update,
         (
             button_appearance_system,
             spawn_screen_fader.run_if(on_event::<SpawnScreenFader>()),
             screen_fade_system,
+            setup_audio_channels.run_if(resource_changed::<Settings>()),
         ),
     );
 
@@ -145,60 +273,35 @@ fn setup_camera(mut commands: Commands) {
     ));
 }
 
-fn setup_assets(
-    mut commands: Commands,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-    asset_server: Res<AssetServer>,
-) {
+fn setup_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
     let ui_assets = UIAssets {
         font: asset_server.load("fonts/Neucha-Regular.ttf"),
         button: asset_server.load("ui/button.png"),
         button_pressed: asset_server.load("ui/button_pressed.png"),
     };
 
-    let player_idle_texture: Handle<Image> = asset_server.load("player/player_idle.png");
-    let player_idle_atlas =
-        TextureAtlas::from_grid(player_idle_texture, Vec2::new(32., 32.), 20, 1, None, None);
-    let player_idle = texture_atlases.add(player_idle_atlas);
-
-    let player_run_texture: Handle<Image> = asset_server.load("player/player_run.png");
-    let player_run_atlas =
-        TextureAtlas::from_grid(player_run_texture, Vec2::new(32., 32.), 5, 1, None, None);
-    let player_run = texture_atlases.add(player_run_atlas);
-
-    let player_jump_texture: Handle<Image> = asset_server.load("player/player_jump.png");
-    let player_jump_atlas =
-        TextureAtlas::from_grid(player_jump_texture, Vec2::new(32., 32.), 5, 1, None, None);
-    let player_jump = texture_atlases.add(player_jump_atlas);
-
-    let player_fall_texture: Handle<Image> = asset_server.load("player/player_fall.png");
-    let player_fall_atlas =
-        TextureAtlas::from_grid(player_fall_texture, Vec2::new(32., 32.), 3, 1, None, None);
-    let player_fall = texture_atlases.add(player_fall_atlas);
-
-    let pill_texture: Handle<Image> = asset_server.load("pill/pill.png");
-    let pill_atlas = TextureAtlas::from_grid(pill_texture, Vec2::new(32., 32.), 45, 1, None, None);
-    let pill = texture_atlases.add(pill_atlas);
-
-    let patient_texture: Handle<Image> = asset_server.load("patient/patient.png");
-    let patient_atlas =
-        TextureAtlas::from_grid(patient_texture, Vec2::new(32., 32.), 4, 1, None, None);
-    let patient = texture_atlases.add(patient_atlas);
-
     let game_assets = GameAssets {
-        player_idle,
-        player_run,
-        player_jump,
-        player_fall,
-        platform: asset_server.load("platform/platform.png"),
-        pill,
-        patient,
+        player_animations: asset_server.load("player/player.anim.json"),
+        // Platforms larger than the sprite repeat the texture to fill their size
+        platform: asset_server.load_with_settings(
+            "platform/platform.png",
+            |settings: &mut ImageLoaderSettings| {
+                settings.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+                    address_mode_u: ImageAddressMode::Repeat,
+                    address_mode_v: ImageAddressMode::Repeat,
+                    ..ImageSamplerDescriptor::nearest()
+                });
+            },
+        ),
+        pill_animations: asset_server.load("pill/pill.anim.json"),
+        patient_animations: asset_server.load("patient/patient.anim.json"),
     };
 
     let audio_assets = AudioAssets {
         bg_music: asset_server.load("music/OST/OST.wav"),
         player_jump: asset_server.load("sounds/jump/jump.wav"),
         pill_collect: asset_server.load("sounds/collect/collect.wav"),
+        footstep: asset_server.load("sounds/footstep/footstep.wav"),
     };
 
     commands.insert_resource(ui_assets);
@@ -206,48 +309,16 @@ fn setup_assets(
     commands.insert_resource(audio_assets);
 }
 
-fn load_game_data(mut commands: Commands) {
-    let game_data_path = "game_data.bin";
-    let config = bincode::config::standard();
-
-    let game_data: GameData = if let Ok(mut file) = std::fs::File::open(game_data_path) {
-        bincode::decode_from_std_read(&mut file, config).unwrap_or_default()
-    } else {
-        let default_game_data = GameData::default();
-        let encoded = bincode::encode_to_vec(&default_game_data, config).unwrap();
-        _ = std::fs::write(game_data_path, &encoded);
-        default_game_data
-    };
-
-    commands.insert_resource(game_data);
-}
-
-fn save_game_data(game_data: Res<GameData>) {
-    let game_data_path = "game_data.bin";
-    let config = bincode::config::standard();
-
-    let encoded = bincode::encode_to_vec(game_data.into_inner(), config).unwrap();
-    std::fs::write(game_data_path, &encoded).unwrap();
-}
-
 fn setup_audio_channels(
+    settings: Res<Settings>,
     bgm: Res<AudioChannel<BackgroundMusicChannel>>,
     sfx: Res<AudioChannel<SFXChannel>>,
 ) {
-    bgm.set_volume(0.6);
-    sfx.set_volume(1.0);
-}
-
-fn next_level_system(
-    mut game_state: ResMut<NextState<GameState>>,
-    mut game_data: ResMut<GameData>,
-    mut events: EventWriter<SaveGameData>,
-) {
-    game_data.current_level += 1;
-    game_state.set(GameState::Level);
-    events.send_default();
+    bgm.set_volume(if settings.music { 0.6 } else { 0. });
+    sfx.set_volume(if settings.sound_effects { 1.0 } else { 0. });
 }
 
+#[allow(clippy::type_complexity)]
 fn button_appearance_system(
     mut query: Query<(&mut UiImage, &Interaction), (With<Button>, Changed<Interaction>)>,
     ui_assets: Res<UIAssets>,
@@ -262,6 +333,36 @@ fn button_appearance_system(
     }
 }
 
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    ui_assets: &UIAssets,
+    text: impl Into<String>,
+    components: impl Bundle,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                image: UiImage::new(ui_assets.button.clone()),
+                style: Style {
+                    padding: UiRect::new(Val::Px(25.), Val::Px(25.), Val::Px(14.), Val::Px(14.)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            components,
+        ))
+        .with_children(|b| {
+            b.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: ui_assets.font.clone(),
+                    font_size: 38.,
+                    color: Color::BLACK,
+                },
+            ));
+        });
+}
+
 fn spawn_screen_fader(mut events: EventReader<SpawnScreenFader>, mut commands: Commands) {
     for event in events.read() {
         commands.spawn((
@@ -286,7 +387,7 @@ fn spawn_screen_fader(mut events: EventReader<SpawnScreenFader>, mut commands: C
 }
 
 fn screen_fade_system(
-    time: Res<Time>,
+    time: Res<Time<Real>>,
     mut game_state: ResMut<NextState<GameState>>,
     mut commands: Commands,
     mut query: Query<(Entity, &mut BackgroundColor, &mut components::ScreenFade)>,
@@ -1,38 +1,76 @@
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 
-use crate::{AudioAssets, BackgroundMusicChannel, GameState, SpawnScreenFader, UIAssets};
+use crate::{
+    save::SaveWarning, settings::spawn_setting_buttons, spawn_button, AudioAssets,
+    BackgroundMusicChannel, GameState, Settings, UIAssets,
+};
+
+#[derive(States, Default, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum MenuScreen {
+    #[default]
+    Closed,
+    Title,
+    SaveSlots,
+    LevelSelect,
+    Leaderboard,
+    Settings,
+}
 
 #[derive(Component)]
 struct MainMenu;
 
+#[derive(Component)]
+struct SettingsMenu;
+
 #[derive(Component)]
 enum ButtonType {
     Play,
+    Settings,
+    Back,
 }
 
 pub struct MainMenuPlugin;
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
-            .add_systems(OnExit(GameState::MainMenu), despawn_main_menu)
+        app.add_state::<MenuScreen>()
+            .add_systems(OnEnter(GameState::MainMenu), open_main_menu)
+            .add_systems(OnExit(GameState::MainMenu), close_main_menu)
+            .add_systems(OnEnter(MenuScreen::Title), spawn_main_menu)
+            .add_systems(OnExit(MenuScreen::Title), despawn_main_menu)
+            .add_systems(OnEnter(MenuScreen::Settings), spawn_settings_menu)
+            .add_systems(OnExit(MenuScreen::Settings), despawn_settings_menu)
             .add_systems(
                 Update,
-                button_action_system.run_if(in_state(GameState::MainMenu)),
+                button_action_system
+                    .run_if(in_state(MenuScreen::Title).or_else(in_state(MenuScreen::Settings))),
             );
     }
 }
 
-fn spawn_main_menu(
-    mut commands: Commands,
+fn open_main_menu(
+    mut menu_screen: ResMut<NextState<MenuScreen>>,
     bgm: Res<AudioChannel<BackgroundMusicChannel>>,
-    ui_assets: Res<UIAssets>,
     audio_assets: Res<AudioAssets>,
 ) {
-    // Plays the background music on repeat
-    bgm.play(audio_assets.bg_music.clone()).looped();
+    // Plays the background music on repeat, unless it is still playing from an earlier visit
+    if !bgm.is_playing_sound() {
+        bgm.play(audio_assets.bg_music.clone()).looped();
+    }
+
+    menu_screen.set(MenuScreen::Title);
+}
 
+fn close_main_menu(mut menu_screen: ResMut<NextState<MenuScreen>>) {
+    menu_screen.set(MenuScreen::Closed);
+}
+
+fn spawn_main_menu(
+    mut commands: Commands,
+    ui_assets: Res<UIAssets>,
+    save_warning: Option<Res<SaveWarning>>,
+) {
     // Spawn in the main menu bundles
     commands
         .spawn((
@@ -61,32 +99,19 @@ fn spawn_main_menu(
                 },
             ));
 
-            n.spawn((
-                ButtonBundle {
-                    image: UiImage::new(ui_assets.button.clone()),
-                    style: Style {
-                        padding: UiRect::new(
-                            Val::Px(25.),
-                            Val::Px(25.),
-                            Val::Px(14.),
-                            Val::Px(14.),
-                        ),
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                },
-                ButtonType::Play,
-            ))
-            .with_children(|b| {
-                b.spawn(TextBundle::from_section(
-                    "Play",
+            spawn_button(n, &ui_assets, "Play", ButtonType::Play);
+            spawn_button(n, &ui_assets, "Settings", ButtonType::Settings);
+
+            if let Some(save_warning) = save_warning {
+                n.spawn(TextBundle::from_section(
+                    save_warning.0.clone(),
                     TextStyle {
                         font: ui_assets.font.clone(),
-                        font_size: 38.,
-                        color: Color::BLACK,
+                        font_size: 24.,
+                        color: Color::MAROON,
                     },
                 ));
-            });
+            }
         });
 }
 
@@ -96,8 +121,47 @@ fn despawn_main_menu(mut commands: Commands, query: Query<Entity, With<MainMenu>
     }
 }
 
+fn spawn_settings_menu(mut commands: Commands, ui_assets: Res<UIAssets>, settings: Res<Settings>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    display: Display::Flex,
+                    row_gap: Val::Px(8.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            SettingsMenu,
+        ))
+        .with_children(|n| {
+            n.spawn(TextBundle::from_section(
+                "Settings",
+                TextStyle {
+                    font: ui_assets.font.clone(),
+                    font_size: 50.,
+                    color: Color::BLACK,
+                },
+            ));
+
+            spawn_setting_buttons(n, &ui_assets, &settings);
+            spawn_button(n, &ui_assets, "Back", ButtonType::Back);
+        });
+}
+
+fn despawn_settings_menu(mut commands: Commands, query: Query<Entity, With<SettingsMenu>>) {
+    if let Ok(entity) = query.get_single() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 fn button_action_system(
-    mut events: EventWriter<SpawnScreenFader>,
+    mut menu_screen: ResMut<NextState<MenuScreen>>,
     query: Query<(&ButtonType, &Interaction), Changed<Interaction>>,
 ) {
     for (btn, interaction) in query.iter() {
@@ -106,11 +170,9 @@ fn button_action_system(
         }
 
         match *btn {
-            ButtonType::Play => events.send(SpawnScreenFader {
-                fade_color: Color::ANTIQUE_WHITE,
-                fade_time: 0.8,
-                next_state: GameState::Level,
-            }),
+            ButtonType::Play => menu_screen.set(MenuScreen::SaveSlots),
+            ButtonType::Settings => menu_screen.set(MenuScreen::Settings),
+            ButtonType::Back => menu_screen.set(MenuScreen::Title),
         }
     }
 }
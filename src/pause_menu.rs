@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::LevelEnding, settings::spawn_setting_buttons, spawn_button, GameState, Settings,
+    SpawnScreenFader, UIAssets,
+};
+
+#[derive(States, Default, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum PauseState {
+    #[default]
+    Unpaused,
+    Paused,
+    Settings,
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component)]
+struct SettingsMenu;
+
+#[derive(Component)]
+enum ButtonType {
+    Resume,
+    RestartLevel,
+    Settings,
+    MainMenu,
+    Back,
+}
+
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<PauseState>()
+            .add_systems(OnEnter(PauseState::Unpaused), unpause_time)
+            .add_systems(OnEnter(PauseState::Paused), (pause_time, spawn_pause_menu))
+            .add_systems(OnExit(PauseState::Paused), despawn_pause_menu)
+            .add_systems(OnEnter(PauseState::Settings), spawn_settings_menu)
+            .add_systems(OnExit(PauseState::Settings), despawn_settings_menu)
+            .add_systems(OnExit(GameState::Level), close_pause_menu)
+            .add_systems(
+                Update,
+                (
+                    pause_toggle_system.run_if(
+                        in_state(GameState::Level).and_then(not(resource_exists::<LevelEnding>())),
+                    ),
+                    button_action_system.run_if(not(in_state(PauseState::Unpaused))),
+                ),
+            );
+    }
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn unpause_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn close_pause_menu(mut pause_state: ResMut<NextState<PauseState>>) {
+    pause_state.set(PauseState::Unpaused);
+}
+
+fn overlay_node() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            display: Display::Flex,
+            row_gap: Val::Px(8.),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        background_color: BackgroundColor(Color::BLACK.with_a(0.6)),
+        z_index: ZIndex::Global(1),
+        ..Default::default()
+    }
+}
+
+fn spawn_pause_menu(mut commands: Commands, ui_assets: Res<UIAssets>) {
+    commands
+        .spawn((overlay_node(), PauseMenu))
+        .with_children(|n| {
+            n.spawn(TextBundle::from_section(
+                "Paused",
+                TextStyle {
+                    font: ui_assets.font.clone(),
+                    font_size: 50.,
+                    color: Color::WHITE,
+                },
+            ));
+
+            spawn_button(n, &ui_assets, "Resume", ButtonType::Resume);
+            spawn_button(n, &ui_assets, "Restart Level", ButtonType::RestartLevel);
+            spawn_button(n, &ui_assets, "Settings", ButtonType::Settings);
+            spawn_button(n, &ui_assets, "Main Menu", ButtonType::MainMenu);
+        });
+}
+
+fn despawn_pause_menu(mut commands: Commands, query: Query<Entity, With<PauseMenu>>) {
+    if let Ok(entity) = query.get_single() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn spawn_settings_menu(mut commands: Commands, ui_assets: Res<UIAssets>, settings: Res<Settings>) {
+    commands
+        .spawn((overlay_node(), SettingsMenu))
+        .with_children(|n| {
+            n.spawn(TextBundle::from_section(
+                "Settings",
+                TextStyle {
+                    font: ui_assets.font.clone(),
+                    font_size: 50.,
+                    color: Color::WHITE,
+                },
+            ));
+
+            spawn_setting_buttons(n, &ui_assets, &settings);
+
+            spawn_button(n, &ui_assets, "Back", ButtonType::Back);
+        });
+}
+
+fn despawn_settings_menu(mut commands: Commands, query: Query<Entity, With<SettingsMenu>>) {
+    if let Ok(entity) = query.get_single() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn pause_toggle_system(
+    kb: Res<Input<KeyCode>>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if kb.just_pressed(KeyCode::Escape) {
+        next_pause_state.set(match pause_state.get() {
+            PauseState::Unpaused => PauseState::Paused,
+            PauseState::Paused => PauseState::Unpaused,
+            PauseState::Settings => PauseState::Paused,
+        });
+    }
+}
+
+fn button_action_system(
+    mut events: EventWriter<SpawnScreenFader>,
+    mut pause_state: ResMut<NextState<PauseState>>,
+    query: Query<(&ButtonType, &Interaction), Changed<Interaction>>,
+) {
+    for (btn, interaction) in query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match *btn {
+            ButtonType::Resume => pause_state.set(PauseState::Unpaused),
+            ButtonType::RestartLevel => events.send(SpawnScreenFader {
+                fade_color: Color::BLACK,
+                fade_time: 0.8,
+                next_state: GameState::Restarting,
+            }),
+            ButtonType::Settings => pause_state.set(PauseState::Settings),
+            ButtonType::MainMenu => events.send(SpawnScreenFader {
+                fade_color: Color::ANTIQUE_WHITE,
+                fade_time: 0.8,
+                next_state: GameState::MainMenu,
+            }),
+            ButtonType::Back => pause_state.set(PauseState::Paused),
+        }
+    }
+}
@@ -0,0 +1,462 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use bincode::{Decode, Encode};
+use thiserror::Error;
+
+use crate::{GameData, LevelRecord, SaveGameData, SaveSlot};
+
+const SAVE_FILE_NAME: &str = "game_data.bin";
+const SAVE_MAGIC: [u8; 4] = *b"EXPD";
+const SAVE_VERSION: u32 = 4;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SaveError {
+    #[error("Could not access save file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Could not encode save data: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+
+    #[error("Could not decode save data: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+
+    #[error("Save file version {0} is newer than this game supports")]
+    UnsupportedVersion(u32),
+}
+
+#[derive(Encode, Decode)]
+struct SaveHeader {
+    magic: [u8; 4],
+    version: u32,
+}
+
+/// The single-profile save format used before save slots, both without a header (version 0) and
+/// with one (version 1).
+#[derive(Decode)]
+struct LegacyGameData {
+    current_level: usize,
+}
+
+impl From<LegacyGameData> for GameData {
+    fn from(data: LegacyGameData) -> Self {
+        let mut slot = SaveSlot::new("Save 1");
+        slot.current_level = data.current_level;
+        slot.unlocked_level = data.current_level;
+
+        Self {
+            slots: vec![slot],
+            active_slot: 0,
+        }
+    }
+}
+
+/// Save slots as written before scores were tracked (version 2).
+#[derive(Decode)]
+struct GameDataV2 {
+    slots: Vec<SaveSlotV2>,
+    active_slot: usize,
+}
+
+#[derive(Decode)]
+struct SaveSlotV2 {
+    name: String,
+    current_level: usize,
+    unlocked_level: usize,
+    level_records: HashMap<usize, LevelRecordV2>,
+}
+
+#[derive(Decode)]
+struct LevelRecordV2 {
+    best_time: Option<f32>,
+    most_pills: u32,
+}
+
+impl From<GameDataV2> for GameData {
+    fn from(data: GameDataV2) -> Self {
+        let slots = data
+            .slots
+            .into_iter()
+            .map(|slot| SaveSlotV3 {
+                name: slot.name,
+                current_level: slot.current_level,
+                unlocked_level: slot.unlocked_level,
+                level_records: slot
+                    .level_records
+                    .into_iter()
+                    .map(|(level, record)| {
+                        let record = LevelRecordV3 {
+                            best_time: record.best_time,
+                            most_pills: record.most_pills,
+                            best_score: 0,
+                            stars: 0,
+                        };
+                        (level, record)
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        GameDataV3 {
+            slots,
+            active_slot: data.active_slot,
+        }
+        .into()
+    }
+}
+
+/// Save slots as written before in-game time and splits were tracked (version 3).
+#[derive(Decode)]
+struct GameDataV3 {
+    slots: Vec<SaveSlotV3>,
+    active_slot: usize,
+}
+
+#[derive(Decode)]
+struct SaveSlotV3 {
+    name: String,
+    current_level: usize,
+    unlocked_level: usize,
+    level_records: HashMap<usize, LevelRecordV3>,
+}
+
+#[derive(Decode)]
+struct LevelRecordV3 {
+    best_time: Option<f32>,
+    most_pills: u32,
+    best_score: u32,
+    stars: u8,
+}
+
+impl From<LevelRecordV3> for LevelRecord {
+    fn from(record: LevelRecordV3) -> Self {
+        Self {
+            best_time: record.best_time,
+            most_pills: record.most_pills,
+            best_score: record.best_score,
+            stars: record.stars,
+        }
+    }
+}
+
+impl From<GameDataV3> for GameData {
+    fn from(data: GameDataV3) -> Self {
+        let slots = data
+            .slots
+            .into_iter()
+            .map(|slot| SaveSlot {
+                name: slot.name,
+                current_level: slot.current_level,
+                unlocked_level: slot.unlocked_level,
+                level_records: slot
+                    .level_records
+                    .into_iter()
+                    .map(|(level, record)| (level, record.into()))
+                    .collect(),
+                in_game_time: 0.,
+                best_splits: Vec::new(),
+            })
+            .collect();
+
+        Self {
+            slots,
+            active_slot: data.active_slot,
+        }
+    }
+}
+
+/// Present when the save file could not be used, so that the game does not write over it. The
+/// message is shown to the player on the main menu.
+#[derive(Resource)]
+pub struct SaveWarning(pub String);
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_game_data).add_systems(
+            Update,
+            save_game_data
+                .run_if(on_event::<SaveGameData>())
+                .run_if(not(resource_exists::<SaveWarning>())),
+        );
+    }
+}
+
+/// Directory that save files are stored in, falling back to the working directory on platforms
+/// without a data directory.
+pub fn save_dir() -> PathBuf {
+    directories::ProjectDirs::from("", "", "Expiry Date")
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .unwrap_or_default()
+}
+
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    path.into()
+}
+
+fn decode_game_data(bytes: &[u8]) -> Result<GameData, SaveError> {
+    let config = bincode::config::standard();
+
+    // Saves without a header predate versioning
+    if !bytes.starts_with(&SAVE_MAGIC) {
+        let (data, _): (LegacyGameData, _) = bincode::decode_from_slice(bytes, config)?;
+        return Ok(data.into());
+    }
+
+    let (header, read): (SaveHeader, _) = bincode::decode_from_slice(bytes, config)?;
+    let payload = &bytes[read..];
+
+    match header.version {
+        1 => {
+            let (data, _): (LegacyGameData, _) = bincode::decode_from_slice(payload, config)?;
+            Ok(data.into())
+        }
+        2 => {
+            let (data, _): (GameDataV2, _) = bincode::decode_from_slice(payload, config)?;
+            Ok(data.into())
+        }
+        3 => {
+            let (data, _): (GameDataV3, _) = bincode::decode_from_slice(payload, config)?;
+            Ok(data.into())
+        }
+        4 => Ok(bincode::decode_from_slice(payload, config)?.0),
+        version => Err(SaveError::UnsupportedVersion(version)),
+    }
+}
+
+fn encode_game_data(game_data: &GameData) -> Result<Vec<u8>, SaveError> {
+    let config = bincode::config::standard();
+    let header = SaveHeader {
+        magic: SAVE_MAGIC,
+        version: SAVE_VERSION,
+    };
+
+    let mut bytes = bincode::encode_to_vec(header, config)?;
+    bytes.extend(bincode::encode_to_vec(game_data, config)?);
+    Ok(bytes)
+}
+
+fn read_game_data(path: &Path) -> Result<GameData, SaveError> {
+    decode_game_data(&fs::read(path)?)
+}
+
+/// Writes to a temporary file before moving it into place, so that a crash mid-write never
+/// leaves a truncated file behind. The previous file is optionally kept as a backup.
+pub fn write_atomic(path: &Path, bytes: &[u8], backup: bool) -> std::io::Result<()> {
+    let temp_path = with_extension(path, "tmp");
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+
+    if backup && path.exists() {
+        fs::copy(path, with_extension(path, "bak"))?;
+    }
+
+    fs::rename(&temp_path, path)
+}
+
+fn write_game_data(dir: &Path, game_data: &GameData) -> Result<(), SaveError> {
+    let bytes = encode_game_data(game_data)?;
+    write_atomic(&dir.join(SAVE_FILE_NAME), &bytes, true)?;
+    Ok(())
+}
+
+/// Returns an error only for saves written by a newer version of the game, which are left
+/// untouched. Any other problem falls back to the backup, or to no save at all.
+fn load_from_dir(dir: &Path) -> Result<Option<GameData>, SaveError> {
+    let path = dir.join(SAVE_FILE_NAME);
+    let backup_path = with_extension(&path, "bak");
+
+    if path.exists() {
+        match read_game_data(&path) {
+            Ok(game_data) => return Ok(Some(game_data)),
+            Err(e @ SaveError::UnsupportedVersion(_)) => return Err(e),
+            Err(e) => {
+                error!("Could not load save file {}: {}", path.display(), e);
+
+                // Keep the broken save around instead of overwriting it on the next save
+                if let Err(e) = fs::rename(&path, with_extension(&path, "corrupt")) {
+                    error!("Could not move aside save file {}: {}", path.display(), e);
+                }
+            }
+        }
+    } else if !backup_path.exists() {
+        // Saves from older versions of the game were written to the working directory
+        return Ok(read_game_data(Path::new(SAVE_FILE_NAME)).ok());
+    }
+
+    match read_game_data(&backup_path) {
+        Ok(game_data) => {
+            warn!("Restored save from backup {}", backup_path.display());
+            Ok(Some(game_data))
+        }
+        Err(e @ SaveError::UnsupportedVersion(_)) => Err(e),
+        Err(e) => {
+            error!(
+                "Could not load backup save file {}: {}",
+                backup_path.display(),
+                e
+            );
+            Ok(None)
+        }
+    }
+}
+
+fn load_game_data(mut commands: Commands) {
+    let dir = save_dir();
+
+    let mut game_data = match load_from_dir(&dir) {
+        Ok(game_data) => game_data.unwrap_or_default(),
+        Err(e) => {
+            error!("Could not load save file from {}: {}", dir.display(), e);
+            commands.insert_resource(SaveWarning(format!("{}. Progress will not be saved.", e)));
+            GameData::default()
+        }
+    };
+
+    game_data.repair();
+    commands.insert_resource(game_data);
+}
+
+fn save_game_data(game_data: Res<GameData>) {
+    let dir = save_dir();
+
+    if let Err(e) = write_game_data(&dir, &game_data) {
+        error!("Could not save game data to {}: {}", dir.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("expiry_date_{}_{}", name, std::process::id()));
+        _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn migrates_headerless_save() {
+        let legacy = bincode::encode_to_vec(3usize, bincode::config::standard()).unwrap();
+        let game_data = decode_game_data(&legacy).unwrap();
+
+        assert_eq!(game_data.slot().current_level, 3);
+        assert_eq!(game_data.slot().unlocked_level, 3);
+    }
+
+    #[test]
+    fn migrates_save_without_scores() {
+        let config = bincode::config::standard();
+        let header = SaveHeader {
+            magic: SAVE_MAGIC,
+            version: 2,
+        };
+        let records = HashMap::from([(1usize, (Some(12.5f32), 1u32))]);
+        let slots = vec![("Test".to_owned(), 2usize, 3usize, records)];
+
+        let mut bytes = bincode::encode_to_vec(header, config).unwrap();
+        bytes.extend(bincode::encode_to_vec((slots, 0usize), config).unwrap());
+
+        let game_data = decode_game_data(&bytes).unwrap();
+        let record = &game_data.slot().level_records[&1];
+
+        assert_eq!(game_data.slot().unlocked_level, 3);
+        assert_eq!(record.best_time, Some(12.5));
+        assert_eq!(record.best_score, 0);
+    }
+
+    #[test]
+    fn migrates_save_without_in_game_time() {
+        let config = bincode::config::standard();
+        let header = SaveHeader {
+            magic: SAVE_MAGIC,
+            version: 3,
+        };
+        let records = HashMap::from([(1usize, (Some(12.5f32), 2u32, 900u32, 2u8))]);
+        let slots = vec![("Test".to_owned(), 2usize, 2usize, records)];
+
+        let mut bytes = bincode::encode_to_vec(header, config).unwrap();
+        bytes.extend(bincode::encode_to_vec((slots, 0usize), config).unwrap());
+
+        let game_data = decode_game_data(&bytes).unwrap();
+
+        assert_eq!(game_data.slot().current_level, 2);
+        assert_eq!(game_data.slot().level_records[&1].best_score, 900);
+        assert_eq!(game_data.slot().in_game_time, 0.);
+        assert!(game_data.slot().best_splits.is_empty());
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let header = SaveHeader {
+            magic: SAVE_MAGIC,
+            version: SAVE_VERSION + 1,
+        };
+        let bytes = bincode::encode_to_vec(header, bincode::config::standard()).unwrap();
+
+        assert!(matches!(
+            decode_game_data(&bytes),
+            Err(SaveError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn falls_back_to_backup_when_corrupted() {
+        let dir = temp_dir("backup");
+
+        let mut game_data = GameData {
+            slots: vec![SaveSlot::new("Test")],
+            active_slot: 0,
+        };
+
+        game_data.slot_mut().current_level = 2;
+        write_game_data(&dir, &game_data).unwrap();
+        game_data.slot_mut().current_level = 4;
+        write_game_data(&dir, &game_data).unwrap();
+        fs::write(dir.join(SAVE_FILE_NAME), SAVE_MAGIC).unwrap();
+
+        let game_data = load_from_dir(&dir).unwrap().unwrap();
+        assert_eq!(game_data.slot().current_level, 2);
+        assert!(dir.join("game_data.bin.corrupt").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn leaves_newer_saves_in_place() {
+        let dir = temp_dir("newer");
+        let header = SaveHeader {
+            magic: SAVE_MAGIC,
+            version: SAVE_VERSION + 1,
+        };
+        let bytes = bincode::encode_to_vec(header, bincode::config::standard()).unwrap();
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(SAVE_FILE_NAME), &bytes).unwrap();
+
+        assert!(matches!(
+            load_from_dir(&dir),
+            Err(SaveError::UnsupportedVersion(_))
+        ));
+        assert_eq!(fs::read(dir.join(SAVE_FILE_NAME)).unwrap(), bytes);
+        assert!(!dir.join("game_data.bin.corrupt").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,275 @@
+use bevy::prelude::*;
+
+use crate::{main_menu::MenuScreen, spawn_button, GameData, SaveGameData, SaveSlot, UIAssets};
+
+pub const MAX_NAME_LENGTH: usize = 16;
+
+#[derive(Resource, Default)]
+struct SaveSlotMenu {
+    renaming: Option<usize>,
+    pending_deletion: Option<usize>,
+}
+
+#[derive(Component)]
+struct SaveSlotList;
+
+#[derive(Component)]
+enum ButtonType {
+    Select(usize),
+    Rename(usize),
+    Delete(usize),
+    ConfirmDelete,
+    CancelDelete,
+    NewSave,
+    Back,
+}
+
+pub struct SaveSlotMenuPlugin;
+
+impl Plugin for SaveSlotMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveSlotMenu>()
+            .add_systems(OnEnter(MenuScreen::SaveSlots), spawn_save_slot_menu)
+            .add_systems(
+                OnExit(MenuScreen::SaveSlots),
+                (despawn_save_slot_menu, reset_save_slot_menu),
+            )
+            .add_systems(
+                Update,
+                (
+                    button_action_system,
+                    rename_system,
+                    (despawn_save_slot_menu, spawn_save_slot_menu)
+                        .chain()
+                        .run_if(
+                            resource_changed::<GameData>()
+                                .or_else(resource_changed::<SaveSlotMenu>()),
+                        ),
+                )
+                    .chain()
+                    .run_if(in_state(MenuScreen::SaveSlots)),
+            );
+    }
+}
+
+fn spawn_save_slot_menu(
+    mut commands: Commands,
+    ui_assets: Res<UIAssets>,
+    game_data: Res<GameData>,
+    menu: Res<SaveSlotMenu>,
+) {
+    let title_style = TextStyle {
+        font: ui_assets.font.clone(),
+        font_size: 50.,
+        color: Color::BLACK,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    display: Display::Flex,
+                    row_gap: Val::Px(8.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            SaveSlotList,
+        ))
+        .with_children(|n| {
+            n.spawn(TextBundle::from_section("Select Save", title_style.clone()));
+
+            for (index, slot) in game_data.slots.iter().enumerate() {
+                n.spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(8.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with_children(|row| {
+                    let label = if menu.renaming == Some(index) {
+                        format!("{}_", slot.name)
+                    } else {
+                        format!("{} - Level {}", slot.name, slot.current_level)
+                    };
+
+                    spawn_button(row, &ui_assets, label, ButtonType::Select(index));
+                    spawn_button(row, &ui_assets, "Rename", ButtonType::Rename(index));
+
+                    // There must always be a slot to play on
+                    if game_data.slots.len() > 1 {
+                        spawn_button(row, &ui_assets, "Delete", ButtonType::Delete(index));
+                    }
+                });
+            }
+
+            n.spawn(NodeBundle {
+                style: Style {
+                    column_gap: Val::Px(8.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with_children(|row| {
+                spawn_button(row, &ui_assets, "New Save", ButtonType::NewSave);
+                spawn_button(row, &ui_assets, "Back", ButtonType::Back);
+            });
+
+            // Deleting a save cannot be undone, so ask for confirmation first
+            if let Some(slot) = menu.pending_deletion.and_then(|i| game_data.slots.get(i)) {
+                n.spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.),
+                        height: Val::Percent(100.),
+                        display: Display::Flex,
+                        row_gap: Val::Px(8.),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(Color::ANTIQUE_WHITE.with_a(0.95)),
+                    z_index: ZIndex::Global(1),
+                    ..Default::default()
+                })
+                .with_children(|dialog| {
+                    dialog.spawn(TextBundle::from_section(
+                        format!("Delete \"{}\"?", slot.name),
+                        title_style.clone(),
+                    ));
+
+                    dialog
+                        .spawn(NodeBundle {
+                            style: Style {
+                                column_gap: Val::Px(8.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        })
+                        .with_children(|row| {
+                            spawn_button(row, &ui_assets, "Delete", ButtonType::ConfirmDelete);
+                            spawn_button(row, &ui_assets, "Cancel", ButtonType::CancelDelete);
+                        });
+                });
+            }
+        });
+}
+
+fn despawn_save_slot_menu(mut commands: Commands, query: Query<Entity, With<SaveSlotList>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn reset_save_slot_menu(mut menu: ResMut<SaveSlotMenu>) {
+    *menu = SaveSlotMenu::default();
+}
+
+fn button_action_system(
+    mut save_events: EventWriter<SaveGameData>,
+    mut menu_screen: ResMut<NextState<MenuScreen>>,
+    mut menu: ResMut<SaveSlotMenu>,
+    mut game_data: ResMut<GameData>,
+    query: Query<(&ButtonType, &Interaction), Changed<Interaction>>,
+) {
+    for (btn, interaction) in query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        // Clicking anywhere else finishes renaming
+        finish_renaming(&mut menu, &mut game_data, &mut save_events);
+
+        match *btn {
+            ButtonType::Select(index) => {
+                game_data.active_slot = index;
+                save_events.send_default();
+                menu_screen.set(MenuScreen::LevelSelect);
+            }
+            ButtonType::Rename(index) => menu.renaming = Some(index),
+            ButtonType::Delete(index) => menu.pending_deletion = Some(index),
+            ButtonType::ConfirmDelete => {
+                if let Some(index) = menu.pending_deletion.take() {
+                    if game_data.slots.len() <= 1 {
+                        continue;
+                    }
+
+                    game_data.slots.remove(index);
+
+                    if game_data.active_slot > index {
+                        game_data.active_slot -= 1;
+                    } else if game_data.active_slot == index {
+                        game_data.active_slot = 0;
+                    }
+
+                    save_events.send_default();
+                }
+            }
+            ButtonType::CancelDelete => menu.pending_deletion = None,
+            ButtonType::NewSave => {
+                let name = game_data.unused_slot_name();
+                game_data.slots.push(SaveSlot::new(name));
+                save_events.send_default();
+            }
+            ButtonType::Back => menu_screen.set(MenuScreen::Title),
+        }
+    }
+}
+
+fn rename_system(
+    kb: Res<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut save_events: EventWriter<SaveGameData>,
+    mut menu: ResMut<SaveSlotMenu>,
+    mut game_data: ResMut<GameData>,
+) {
+    let Some(index) = menu.renaming else {
+        characters.clear();
+        return;
+    };
+
+    let mut name = game_data.slots[index].name.clone();
+
+    for event in characters.read() {
+        if !event.char.is_control() && name.chars().count() < MAX_NAME_LENGTH {
+            name.push(event.char);
+        }
+    }
+
+    if kb.just_pressed(KeyCode::Back) {
+        name.pop();
+    }
+
+    // Only touch the save data when the name actually changes, since that rebuilds the menu
+    if name != game_data.slots[index].name {
+        game_data.slots[index].name = name;
+    }
+
+    if kb.just_pressed(KeyCode::Return) {
+        finish_renaming(&mut menu, &mut game_data, &mut save_events);
+    }
+}
+
+/// Saves the name of the slot being renamed, giving it a default name if it was left blank.
+fn finish_renaming(
+    menu: &mut SaveSlotMenu,
+    game_data: &mut GameData,
+    save_events: &mut EventWriter<SaveGameData>,
+) {
+    let Some(index) = menu.renaming.take() else {
+        return;
+    };
+
+    if game_data.slots[index].name.trim().is_empty() {
+        game_data.slots[index].name = game_data.unused_slot_name();
+    }
+
+    save_events.send_default();
+}
@@ -0,0 +1,231 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use bincode::{Decode, Encode};
+use thiserror::Error;
+
+use crate::{
+    save::{save_dir, write_atomic},
+    spawn_button, Settings, UIAssets,
+};
+
+const SETTINGS_FILE_NAME: &str = "settings.bin";
+const SETTINGS_MAGIC: [u8; 4] = *b"EXPS";
+const SETTINGS_VERSION: u32 = 1;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Could not access settings file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Could not encode settings: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+
+    #[error("Could not decode settings: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+
+    #[error("Not a settings file")]
+    InvalidFile,
+
+    #[error("Settings version {0} is newer than this game supports")]
+    UnsupportedVersion(u32),
+}
+
+#[derive(Encode, Decode)]
+struct SettingsHeader {
+    magic: [u8; 4],
+    version: u32,
+}
+
+/// A button that switches a setting on or off, shared by the main menu and the pause menu.
+#[derive(Component, Clone, Copy)]
+pub enum SettingToggle {
+    Music,
+    SoundEffects,
+    Ghost,
+    SpeedrunTimer,
+    ScreenShake,
+    Minimap,
+}
+
+impl SettingToggle {
+    const ALL: [SettingToggle; 6] = [
+        SettingToggle::Music,
+        SettingToggle::SoundEffects,
+        SettingToggle::Ghost,
+        SettingToggle::SpeedrunTimer,
+        SettingToggle::ScreenShake,
+        SettingToggle::Minimap,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            SettingToggle::Music => "Music",
+            SettingToggle::SoundEffects => "Sound Effects",
+            SettingToggle::Ghost => "Ghost Runner",
+            SettingToggle::SpeedrunTimer => "Speedrun Timer",
+            SettingToggle::ScreenShake => "Screen Shake",
+            SettingToggle::Minimap => "Minimap",
+        }
+    }
+
+    fn enabled(&self, settings: &Settings) -> bool {
+        match self {
+            SettingToggle::Music => settings.music,
+            SettingToggle::SoundEffects => settings.sound_effects,
+            SettingToggle::Ghost => settings.ghost,
+            SettingToggle::SpeedrunTimer => settings.speedrun_timer,
+            SettingToggle::ScreenShake => settings.screen_shake,
+            SettingToggle::Minimap => settings.minimap,
+        }
+    }
+
+    fn toggle(&self, settings: &mut Settings) {
+        match self {
+            SettingToggle::Music => settings.music = !settings.music,
+            SettingToggle::SoundEffects => settings.sound_effects = !settings.sound_effects,
+            SettingToggle::Ghost => settings.ghost = !settings.ghost,
+            SettingToggle::SpeedrunTimer => settings.speedrun_timer = !settings.speedrun_timer,
+            SettingToggle::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            SettingToggle::Minimap => settings.minimap = !settings.minimap,
+        }
+    }
+
+    fn text(&self, settings: &Settings) -> String {
+        let enabled = if self.enabled(settings) { "On" } else { "Off" };
+        format!("{}: {}", self.name(), enabled)
+    }
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_settings).add_systems(
+            Update,
+            (
+                setting_toggle_system,
+                (
+                    setting_label_system,
+                    save_settings.run_if(not(resource_added::<Settings>())),
+                )
+                    .run_if(resource_changed::<Settings>()),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Spawns a toggle button for every setting.
+pub fn spawn_setting_buttons(parent: &mut ChildBuilder, ui_assets: &UIAssets, settings: &Settings) {
+    for toggle in SettingToggle::ALL {
+        spawn_button(parent, ui_assets, toggle.text(settings), toggle);
+    }
+}
+
+fn decode_settings(bytes: &[u8]) -> Result<Settings, SettingsError> {
+    if !bytes.starts_with(&SETTINGS_MAGIC) {
+        return Err(SettingsError::InvalidFile);
+    }
+
+    let config = bincode::config::standard();
+    let (header, read): (SettingsHeader, _) = bincode::decode_from_slice(bytes, config)?;
+
+    match header.version {
+        1 => Ok(bincode::decode_from_slice(&bytes[read..], config)?.0),
+        version => Err(SettingsError::UnsupportedVersion(version)),
+    }
+}
+
+fn encode_settings(settings: &Settings) -> Result<Vec<u8>, SettingsError> {
+    let config = bincode::config::standard();
+    let header = SettingsHeader {
+        magic: SETTINGS_MAGIC,
+        version: SETTINGS_VERSION,
+    };
+
+    let mut bytes = bincode::encode_to_vec(header, config)?;
+    bytes.extend(bincode::encode_to_vec(settings, config)?);
+    Ok(bytes)
+}
+
+fn read_settings(path: &Path) -> Result<Settings, SettingsError> {
+    decode_settings(&fs::read(path)?)
+}
+
+fn write_settings(path: &Path, settings: &Settings) -> Result<(), SettingsError> {
+    write_atomic(path, &encode_settings(settings)?, false)?;
+    Ok(())
+}
+
+fn load_settings(mut commands: Commands) {
+    let path = save_dir().join(SETTINGS_FILE_NAME);
+
+    // Settings are easy to set again, so any problem just falls back to the defaults
+    let settings = match read_settings(&path) {
+        Ok(settings) => settings,
+        Err(SettingsError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            Settings::default()
+        }
+        Err(e) => {
+            error!("Could not load settings from {}: {}", path.display(), e);
+            Settings::default()
+        }
+    };
+
+    commands.insert_resource(settings);
+}
+
+fn save_settings(settings: Res<Settings>) {
+    let path = save_dir().join(SETTINGS_FILE_NAME);
+
+    if let Err(e) = write_settings(&path, &settings) {
+        error!("Could not save settings to {}: {}", path.display(), e);
+    }
+}
+
+fn setting_toggle_system(
+    mut settings: ResMut<Settings>,
+    query: Query<(&SettingToggle, &Interaction), Changed<Interaction>>,
+) {
+    for (toggle, interaction) in query.iter() {
+        if *interaction == Interaction::Pressed {
+            toggle.toggle(&mut settings);
+        }
+    }
+}
+
+fn setting_label_system(
+    settings: Res<Settings>,
+    toggle_query: Query<(&SettingToggle, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (toggle, children) in toggle_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = toggle.text(&settings);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_settings() {
+        let settings = Settings {
+            screen_shake: false,
+            minimap: true,
+            ..Default::default()
+        };
+
+        let decoded = decode_settings(&encode_settings(&settings).unwrap()).unwrap();
+
+        assert!(!decoded.screen_shake);
+        assert!(decoded.minimap);
+        assert!(decoded.music);
+    }
+}